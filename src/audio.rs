@@ -0,0 +1,187 @@
+#[cfg(test)]
+use std::cell::RefCell;
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    process::Command,
+    thread,
+};
+
+use crate::timer::TimerEvent;
+
+pub trait AudioBackend: fmt::Debug {
+    fn speak(&self, text: &str);
+    fn play(&self, path: &Path);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cue {
+    RoundStart,
+    RoundEnd,
+    Warning,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Sounds {
+    pub round_start: Option<PathBuf>,
+    pub round_end: Option<PathBuf>,
+    pub warning: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone)]
+pub struct CommandLine {
+    program: String,
+    args: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct ProcessBackend {
+    speaker: Option<CommandLine>,
+    player: Option<CommandLine>,
+}
+
+const ROUND_START: &str = "round_start.wav";
+const ROUND_END: &str = "round_end.wav";
+const WARNING: &str = "warning.wav";
+
+impl Cue {
+    pub fn from_event(event: &TimerEvent) -> Option<Cue> {
+        match event {
+            TimerEvent::RoundStarted(_) => Some(Cue::RoundStart),
+            TimerEvent::Warning(_) => Some(Cue::Warning),
+            TimerEvent::RoundEnded(_) => Some(Cue::RoundEnd),
+            TimerEvent::Finished => None,
+        }
+    }
+}
+
+impl Sounds {
+    // Files missing in the directory stay muted.
+    pub fn from_dir(dir: &Path) -> Sounds {
+        let existing = |name: &str| {
+            let path = dir.join(name);
+            if path.is_file() { Some(path) } else { None }
+        };
+        Sounds {
+            round_start: existing(ROUND_START),
+            round_end: existing(ROUND_END),
+            warning: existing(WARNING),
+        }
+    }
+
+    pub fn get(&self, cue: Cue) -> Option<&Path> {
+        match cue {
+            Cue::RoundStart => self.round_start.as_deref(),
+            Cue::RoundEnd => self.round_end.as_deref(),
+            Cue::Warning => self.warning.as_deref(),
+        }
+    }
+}
+
+impl CommandLine {
+    // The command line is split on whitespace, e.g. "espeak -s 200".
+    pub fn new(command: &str) -> Option<CommandLine> {
+        let mut parts = command.split_whitespace().map(str::to_owned);
+        let program = parts.next()?;
+        Some(CommandLine {
+            program,
+            args: parts.collect(),
+        })
+    }
+
+    // Wait for the process on its own thread, the next sound must not block
+    // the UI but finished processes must be reaped.
+    fn spawn(&self, arg: impl AsRef<std::ffi::OsStr>) {
+        match Command::new(&self.program)
+            .args(&self.args)
+            .arg(arg)
+            .spawn()
+        {
+            Ok(mut child) => {
+                thread::spawn(move || child.wait());
+            }
+            Err(e) => eprintln!("Couldn't start {}: {}", self.program, e),
+        }
+    }
+}
+
+impl ProcessBackend {
+    pub fn new(speaker: Option<CommandLine>, player: Option<CommandLine>) -> ProcessBackend {
+        ProcessBackend { speaker, player }
+    }
+}
+
+// Records what would be spoken and played instead, for tests.
+#[cfg(test)]
+#[derive(Debug, Default)]
+pub struct NullBackend {
+    spoken: RefCell<Vec<String>>,
+    played: RefCell<Vec<PathBuf>>,
+}
+
+#[cfg(test)]
+impl NullBackend {
+    pub fn spoken(&self) -> Vec<String> {
+        self.spoken.borrow().clone()
+    }
+
+    pub fn played(&self) -> Vec<PathBuf> {
+        self.played.borrow().clone()
+    }
+}
+
+#[cfg(test)]
+impl AudioBackend for NullBackend {
+    fn speak(&self, text: &str) {
+        self.spoken.borrow_mut().push(text.to_owned());
+    }
+
+    fn play(&self, path: &Path) {
+        self.played.borrow_mut().push(path.to_owned());
+    }
+}
+
+impl AudioBackend for ProcessBackend {
+    fn speak(&self, text: &str) {
+        if let Some(speaker) = &self.speaker {
+            speaker.spawn(text);
+        }
+    }
+
+    fn play(&self, path: &Path) {
+        if let Some(player) = &self.player {
+            player.spawn(path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_line() {
+        let command = CommandLine::new("espeak -s 200").unwrap();
+        assert_eq!(command.program, "espeak");
+        assert_eq!(command.args, vec!["-s".to_owned(), "200".to_owned()]);
+        assert!(CommandLine::new("  ").is_none());
+    }
+
+    #[test]
+    fn test_bundled_sounds() {
+        let sounds = Sounds::from_dir(Path::new("./sounds"));
+        assert_eq!(
+            sounds.get(Cue::RoundStart),
+            Some(Path::new("./sounds/round_start.wav"))
+        );
+        assert!(sounds.get(Cue::RoundEnd).is_some());
+        assert!(sounds.get(Cue::Warning).is_some());
+        assert_eq!(Sounds::from_dir(Path::new("./missing")), Sounds::default());
+    }
+
+    #[test]
+    fn test_cue_from_event() {
+        assert_eq!(Cue::from_event(&TimerEvent::Warning(1)), Some(Cue::Warning));
+        assert_eq!(Cue::from_event(&TimerEvent::Finished), None);
+    }
+}
@@ -1,11 +1,17 @@
 use std::{
-    env, fmt, error,
-    fs::File,
-    io::{self, BufRead},
+    fmt, error, fs, io, mem,
+    path::{Path, PathBuf},
     rc::Rc,
 };
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+use serde::{Deserialize, Serialize};
+
+use crate::format::{self, Format};
+use crate::notation::{self, Notation, NotationError};
+use crate::tags;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Distance {
     Short,
     Long,
@@ -29,6 +35,13 @@ pub enum Body {
     No,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Stance {
+    Orthodox,
+    Southpaw,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Combination {
     pub description: String,
@@ -37,21 +50,91 @@ pub struct Combination {
     pub faint: Faint,
     pub body: Body,
     pub url: Option<String>,
+    pub library: String,
+    // The file the combination was loaded from, empty if it is in none yet.
+    pub source: PathBuf,
+    pub difficulty: Option<u8>,
+    pub tags: Vec<String>,
+    pub notes: Option<String>,
+    pub stance: Option<Stance>,
+    pub comments: Vec<String>,
+    pub derived: Derived,
+    // The text of the legacy file the combination was loaded from: the
+    // comments and blank lines above it and its own line, as they were
+    // written. Empty if it is not from a legacy file.
+    pub raw: String,
+}
+
+// The Yes/No flags left to the notation by "auto" columns or missing
+// fields, so that they are written back that way.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Derived {
+    pub defense: bool,
+    pub faint: bool,
+    pub body: bool,
 }
 
 #[derive(Debug)]
 pub enum CombinationError {
     IoError(std::io::Error),
-    ParseError(String),
+    ParseError(ParseError),
+    Errors(Vec<ParseError>),
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ParseError {
+    pub file: Option<PathBuf>,
+    pub line: Option<usize>,
+    pub field: Option<usize>,
+    pub message: String,
+    pub expected: Vec<&'static str>,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Consistency {
+    Warn,
+    Strict,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LoadOptions {
+    pub consistency: Consistency,
+    pub skip_invalid: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Loaded {
+    pub data: Vec<Rc<Combination>>,
+    pub errors: Vec<ParseError>,
+    // Flags that don't match the notation, with Consistency::Warn.
+    pub warnings: Vec<ParseError>,
+    // Comment lines after the last combination; the ones before a
+    // combination are kept in Combination::comments.
+    pub comments: Vec<String>,
+    // The text of legacy files after their last combination, see
+    // Combination::raw.
+    pub raw: String,
+}
+
+pub type Parsed = Result<Combination, ParseError>;
+
 const FIELD_COUNT : usize = 6;
-const DELIMITER : &str = ";";
+const TAGS_FIELD_COUNT : usize = 7;
+pub const DELIMITER : &str = ";";
 const COMMENT : &str = "#";
-const SHORT : &str = "short";
-const LONG : &str = "long";
+pub const SHORT : &str = "short";
+pub const LONG : &str = "long";
 const YES : &str = "yes";
 const NO : &str = "no";
+const AUTO : &str = "auto";
+const DESCRIPTION_FIELD : usize = 1;
+const DISTANCE_FIELD : usize = 2;
+const DEFENSE_FIELD : usize = 3;
+const FAINT_FIELD : usize = 4;
+const BODY_FIELD : usize = 5;
+pub const DEFENSE_TAG : &str = "defense";
+pub const FAINT_TAG : &str = "faint";
+pub const BODY_TAG : &str = "body";
 
 impl Combination {
     fn new(
@@ -60,7 +143,8 @@ impl Combination {
         defense: Defense,
         faint: Faint,
         body: Body,
-        url: Option<String>
+        url: Option<String>,
+        library: String,
     ) -> Combination {
         Combination {
             description,
@@ -69,7 +153,205 @@ impl Combination {
             faint,
             body,
             url,
+            library,
+            source: PathBuf::new(),
+            difficulty: None,
+            tags: vec![],
+            notes: None,
+            stance: None,
+            comments: vec![],
+            derived: Derived::default(),
+            raw: String::new(),
+        }
+    }
+
+    // The flags are derived from the notation of the description.
+    pub fn from_notation(
+        description: String,
+        notation: &Notation,
+        distance: Distance,
+        library: &str,
+    ) -> Combination {
+        Combination::new(
+            description,
+            distance,
+            if notation.has_defense() { Defense::Yes } else { Defense::No },
+            if notation.has_feint() { Faint::Yes } else { Faint::No },
+            if notation.has_body() { Body::Yes } else { Body::No },
+            None,
+            library.to_owned(),
+        )
+    }
+
+    pub fn notation(&self) -> Result<Notation, NotationError> {
+        notation::parse(&self.description)
+    }
+
+    pub fn inconsistencies(&self) -> Vec<ParseError> {
+        let notation = match self.notation() {
+            Ok(notation) => notation,
+            Err(e) => {
+                return vec![ParseError::at_field(
+                    DESCRIPTION_FIELD,
+                    format!("Cannot check flags of {:?}: {}", self.description, e),
+                    &[],
+                )];
+            }
+        };
+        let checks = [
+            (DEFENSE_FIELD, "defense", self.defense == Defense::Yes, notation.has_defense()),
+            (FAINT_FIELD, "faint", self.faint == Faint::Yes, notation.has_feint()),
+            (BODY_FIELD, "body", self.body == Body::Yes, notation.has_body()),
+        ];
+        let mut result = vec![];
+        for (field, name, declared, derived) in checks {
+            if declared != derived {
+                result.push(ParseError::at_field(
+                    field,
+                    format!(
+                        "Declared {} {} but notation says {} in {:?}",
+                        name,
+                        yes_no(declared),
+                        yes_no(derived),
+                        self.description
+                    ),
+                    &[],
+                ));
+            }
         }
+        result
+    }
+}
+
+impl Combination {
+    pub fn has_metadata(&self) -> bool {
+        self.difficulty.is_some() || self.notes.is_some() || self.stance.is_some()
+    }
+
+    // The explicit tags plus the ones implied by distance and the Yes/No flags.
+    pub fn all_tags(&self) -> Vec<String> {
+        let mut tags = self.tags.clone();
+        let distance = match self.distance {
+            Distance::Long => LONG,
+            Distance::Short => SHORT,
+        };
+        tags.push(distance.to_owned());
+        for (tag, set) in [
+            (DEFENSE_TAG, self.defense == Defense::Yes),
+            (FAINT_TAG, self.faint == Faint::Yes),
+            (BODY_TAG, self.body == Body::Yes),
+        ] {
+            if set {
+                tags.push(tag.to_owned());
+            }
+        }
+        tags
+    }
+
+    // The legacy format has no columns for difficulty, notes and stance.
+    pub fn to_line(&self) -> String {
+        let columns = [
+            self.description.clone(),
+            self.distance.to_string(),
+            column(self.derived.defense, self.defense == Defense::Yes),
+            column(self.derived.faint, self.faint == Faint::Yes),
+            column(self.derived.body, self.body == Body::Yes),
+        ];
+        let mut line = columns.join(&format!("{} ", DELIMITER));
+        line.push_str(DELIMITER);
+        if let Some(url) = &self.url {
+            line.push(' ');
+            line.push_str(url);
+        }
+        if !self.tags.is_empty() {
+            line.push_str(DELIMITER);
+            line.push(' ');
+            line.push_str(&self.tags.join(", "));
+        }
+        line
+    }
+}
+
+// For tests: a long combination of the basics library with the flags derived
+// from the description. Invalid notation gives No flags.
+#[cfg(test)]
+impl Combination {
+    pub fn from_description(description: &str) -> Combination {
+        let notation = notation::parse(description).ok();
+        let derived = |has: fn(&Notation) -> bool| notation.as_ref().is_some_and(has);
+        Combination::new(
+            description.to_owned(),
+            Distance::Long,
+            if derived(Notation::has_defense) { Defense::Yes } else { Defense::No },
+            if derived(Notation::has_feint) { Faint::Yes } else { Faint::No },
+            if derived(Notation::has_body) { Body::Yes } else { Body::No },
+            None,
+            "basics".to_owned(),
+        )
+    }
+}
+
+impl ParseError {
+    fn at_field(field: usize, message: String, expected: &[&'static str]) -> ParseError {
+        ParseError {
+            field: Some(field),
+            message,
+            expected: expected.to_vec(),
+            ..ParseError::default()
+        }
+    }
+
+    fn located(mut self, file: &Path, line: Option<usize>) -> ParseError {
+        self.file = Some(file.to_owned());
+        self.line = self.line.or(line);
+        self
+    }
+}
+
+impl CombinationError {
+    pub fn messages(&self) -> Vec<String> {
+        match self {
+            CombinationError::Errors(errors) => errors.iter().map(|e| e.to_string()).collect(),
+            e => vec![e.to_string()],
+        }
+    }
+}
+
+impl fmt::Display for Distance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Distance::Short => write!(f, "Short"),
+            Distance::Long => write!(f, "Long"),
+        }
+    }
+}
+
+impl fmt::Display for Stance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Stance::Orthodox => write!(f, "Orthodox"),
+            Stance::Southpaw => write!(f, "Southpaw"),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(file) = &self.file {
+            write!(f, "{}:", file.display())?;
+            if let Some(line) = self.line {
+                write!(f, "{}:", line)?;
+            }
+            write!(f, " ")?;
+        }
+        if let Some(field) = self.field {
+            write!(f, "field {}: ", field)?;
+        }
+        write!(f, "{}", self.message)?;
+        if !self.expected.is_empty() {
+            write!(f, " (expected {})", self.expected.join("|"))?;
+        }
+        Ok(())
     }
 }
 
@@ -78,6 +360,13 @@ impl fmt::Display for CombinationError {
         match self {
             CombinationError::IoError(e) => write!(f, "I/O error: {}", e),
             CombinationError::ParseError(e) => write!(f, "Parse error: {}", e),
+            CombinationError::Errors(errors) => {
+                write!(f, "{} parse errors", errors.len())?;
+                for e in errors {
+                    write!(f, "\n{}", e)?;
+                }
+                Ok(())
+            }
         }
     }
 }
@@ -88,38 +377,185 @@ impl From<io::Error> for CombinationError {
     }
 }
 
+impl From<ParseError> for CombinationError {
+    fn from(error: ParseError) -> Self {
+        CombinationError::ParseError(error)
+    }
+}
+
 impl error::Error for CombinationError {
     fn source(&self) -> Option<&(dyn error::Error + 'static)> {
         match self {
             CombinationError::IoError(e) => Some(e),
-            CombinationError::ParseError(_) => None,
+            CombinationError::ParseError(_) | CombinationError::Errors(_) => None,
         }
     }
 }
 
-pub fn load_data(path: &str) -> Result<Vec<Rc<Combination>>, CombinationError> {
-    println!("CWD is {:?}, path is {:?}", env::current_dir()?, path);
-    let mut data: Vec<Rc<Combination>> = vec![];
-    let file = File::open(path)?;
-    let reader = io::BufReader::new(file);
-    for line in reader.lines() {
-        let line = line?.trim().to_owned();
-        if line.starts_with(COMMENT) || line.is_empty() {
+pub fn load_libraries(paths: &[PathBuf], options: LoadOptions) -> Result<Loaded, CombinationError> {
+    let mut loaded = Loaded::default();
+    let mut errors = vec![];
+    let skipping = LoadOptions {
+        skip_invalid: true,
+        ..options
+    };
+    for path in paths {
+        let library = load_data(path, skipping)?;
+        loaded.data.extend(library.data);
+        loaded.comments.extend(library.comments);
+        loaded.raw.push_str(&library.raw);
+        loaded.warnings.extend(library.warnings);
+        errors.extend(library.errors);
+    }
+    if !errors.is_empty() && !options.skip_invalid {
+        return Err(CombinationError::Errors(errors));
+    }
+    loaded.errors = errors;
+    Ok(loaded)
+}
+
+pub fn library_name(path: &Path) -> String {
+    match path.file_stem() {
+        Some(stem) => stem.to_string_lossy().into_owned(),
+        None => path.to_string_lossy().into_owned(),
+    }
+}
+
+// All combinations are checked in one pass. Invalid ones are either reported
+// together as CombinationError::Errors or skipped and returned in Loaded::errors.
+// Files ending in .toml or .json use the structured formats, anything else the
+// legacy semicolon delimited lines.
+pub fn load_data(path: &Path, options: LoadOptions) -> Result<Loaded, CombinationError> {
+    let library = library_name(path);
+    let mut loaded = Loaded::default();
+    let content = fs::read_to_string(path)
+        .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path.display(), e)))?;
+    let parsed: Vec<(Option<usize>, Parsed)> = match Format::from_path(path) {
+        Format::Legacy => {
+            let mut parsed = vec![];
+            let mut comments = vec![];
+            let mut raw = String::new();
+            for (index, text) in content.split_inclusive('\n').enumerate() {
+                raw.push_str(text);
+                let line = text.trim();
+                if let Some(comment) = line.strip_prefix(COMMENT) {
+                    comments.push(comment.trim().to_owned());
+                } else if !line.is_empty() {
+                    let result = parse_combination(line, &library).map(|combination| Combination {
+                        comments: mem::take(&mut comments),
+                        raw: mem::take(&mut raw),
+                        ..combination
+                    });
+                    parsed.push((Some(index + 1), result));
+                }
+            }
+            loaded.comments = comments;
+            loaded.raw = raw;
+            parsed
+        }
+        format @ (Format::Toml | Format::Json) => {
+            let (parsed, comments) = format::parse_structured(format, &content, &library);
+            loaded.comments = comments;
+            parsed.into_iter().map(|result| (None, result)).collect()
+        }
+        format => {
+            return Err(ParseError {
+                file: Some(path.to_owned()),
+                message: format!("{} files can only be written", format),
+                ..ParseError::default()
+            }
+            .into());
+        }
+    };
+    for (line, result) in parsed {
+        let combination = match result {
+            Ok(combination) => Rc::new(Combination {
+                source: path.to_owned(),
+                ..combination
+            }),
+            Err(e) => {
+                loaded.errors.push(e.located(path, line));
+                continue;
+            }
+        };
+        let inconsistencies = combination.inconsistencies();
+        if options.consistency == Consistency::Strict && !inconsistencies.is_empty() {
+            loaded
+                .errors
+                .extend(inconsistencies.into_iter().map(|e| e.located(path, line)));
             continue;
         }
-        let combination = parse_combination(&line)?;
-        data.push(combination);
+        loaded
+            .warnings
+            .extend(inconsistencies.into_iter().map(|e| e.located(path, line)));
+        loaded.data.push(combination);
     }
-    Ok(data)
+    if !loaded.errors.is_empty() && !options.skip_invalid {
+        return Err(CombinationError::Errors(loaded.errors));
+    }
+    Ok(loaded)
+}
+
+// Lines of legacy files that are neither blank nor a comment.
+pub fn is_legacy_combination(line: &str) -> bool {
+    let line = line.trim();
+    !line.is_empty() && !line.starts_with(COMMENT)
 }
 
-fn parse_combination(line: &str) -> Result<Rc<Combination>, CombinationError> {
+// Combinations read from legacy files are written as they were read, the
+// others and the comments of structured files in the canonical form.
+pub fn write_legacy(loaded: &Loaded) -> String {
+    let mut content = String::new();
+    for combination in &loaded.data {
+        if combination.raw.is_empty() {
+            let mut text = String::new();
+            write_comments(&mut text, &combination.comments);
+            text.push_str(&combination.to_line());
+            text.push('\n');
+            write_raw(&mut content, &text);
+        } else {
+            write_raw(&mut content, &combination.raw);
+        }
+    }
+    if loaded.raw.is_empty() {
+        let mut text = String::new();
+        write_comments(&mut text, &loaded.comments);
+        write_raw(&mut content, &text);
+    } else {
+        write_raw(&mut content, &loaded.raw);
+    }
+    content
+}
+
+// The last line of a file may lack its line break.
+fn write_raw(content: &mut String, text: &str) {
+    if !content.is_empty() && !content.ends_with('\n') && !text.is_empty() {
+        content.push('\n');
+    }
+    content.push_str(text);
+}
+
+fn write_comments(content: &mut String, comments: &[String]) {
+    for comment in comments {
+        content.push_str(COMMENT);
+        if !comment.is_empty() {
+            content.push(' ');
+            content.push_str(comment);
+        }
+        content.push('\n');
+    }
+}
+
+fn parse_combination(line: &str, library: &str) -> Result<Combination, ParseError> {
     let el: Vec<&str> = line.split(DELIMITER).collect();
-    if el.len() != FIELD_COUNT {
-        return Err(CombinationError::ParseError(format!(
-            "Expect {} elements delimited by {} in {:?}",
-            FIELD_COUNT, DELIMITER, line
-        )));
+    if el.len() != FIELD_COUNT && el.len() != TAGS_FIELD_COUNT {
+        return Err(ParseError {
+            message: format!(
+                "Expect {} or {} elements delimited by {} in {:?}",
+                FIELD_COUNT, TAGS_FIELD_COUNT, DELIMITER, line
+            ),
+            ..ParseError::default()
+        });
     }
     let description = el[0].trim().to_owned();
     let distance = el[1].trim();
@@ -128,55 +564,105 @@ fn parse_combination(line: &str) -> Result<Rc<Combination>, CombinationError> {
     } else if distance.eq_ignore_ascii_case(SHORT) {
         Distance::Short
     } else {
-        return Err(CombinationError::ParseError(format!(
-            "Unknown distance {:?} in {:?}",
-            distance, line
-        )));
-    };
-    let defense = el[2];
-    let defense = match parse_yes_no(defense, Defense::Yes, Defense::No) {
-        Some(val) => val,
-        None => {
-            return Err(CombinationError::ParseError(format!(
-                "Unknown defense {:?} in {:?}",
-                defense, line
-            )));
-        }
-    };
-    let faint = el[3];
-    let faint = match parse_yes_no(faint, Faint::Yes, Faint::No) {
-        Some(val) => val,
-        None => {
-            return Err(CombinationError::ParseError(format!(
-                "Unknown faint {:?} in {:?}",
-                faint, line
-            )));
-        }
+        return Err(ParseError::at_field(
+            DISTANCE_FIELD,
+            format!("Unknown distance {:?} in {:?}", distance, line),
+            &[LONG, SHORT],
+        ));
     };
-    let body = el[4];
-    let body = match parse_yes_no(body, Body::Yes, Body::No) {
-        Some(val) => val,
-        None => {
-            return Err(CombinationError::ParseError(format!(
-                "Unknown body {:?} in {:?}",
-                body, line
-            )));
-        }
-    };
-    let url = el[5];
+    // Yes/No columns may be "auto" (or empty) to be derived from the notation.
+    let notation = notation::parse(&description);
+    let (defense, derived_defense) = parse_column(
+        DEFENSE_FIELD,
+        "defense",
+        el[2],
+        Defense::Yes,
+        Defense::No,
+        notation.as_ref().map(Notation::has_defense),
+        line,
+    )?;
+    let (faint, derived_faint) = parse_column(
+        FAINT_FIELD,
+        "faint",
+        el[3],
+        Faint::Yes,
+        Faint::No,
+        notation.as_ref().map(Notation::has_feint),
+        line,
+    )?;
+    let (body, derived_body) = parse_column(
+        BODY_FIELD,
+        "body",
+        el[4],
+        Body::Yes,
+        Body::No,
+        notation.as_ref().map(Notation::has_body),
+        line,
+    )?;
+    let url = el[5].trim();
     let url = if url.is_empty() {
         None
     } else {
-        Some(url.trim().to_owned())
+        Some(url.to_owned())
     };
-    Ok(Rc::new(Combination::new(
+    let mut combination = Combination::new(
         description,
         distance,
         defense,
         faint,
         body,
         url,
-    )))
+        library.to_owned(),
+    );
+    combination.derived = Derived {
+        defense: derived_defense,
+        faint: derived_faint,
+        body: derived_body,
+    };
+    // An optional last column holds comma separated tags.
+    if let Some(tags) = el.get(FIELD_COUNT) {
+        combination.tags = tags::parse(tags);
+    }
+    Ok(combination)
+}
+
+fn parse_column<T>(
+    index: usize,
+    name: &str,
+    field: &str,
+    yes: T,
+    no: T,
+    derived: Result<bool, &NotationError>,
+    line: &str,
+) -> Result<(T, bool), ParseError> {
+    let trimmed = field.trim();
+    if trimmed.is_empty() || trimmed.eq_ignore_ascii_case(AUTO) {
+        return match derived {
+            Ok(true) => Ok((yes, true)),
+            Ok(false) => Ok((no, true)),
+            Err(e) => Err(ParseError::at_field(
+                index,
+                format!("Cannot derive {} ({}) in {:?}", name, e, line),
+                &[],
+            )),
+        };
+    }
+    match parse_yes_no(field, yes, no) {
+        Some(val) => Ok((val, false)),
+        None => Err(ParseError::at_field(
+            index,
+            format!("Unknown {} {:?} in {:?}", name, field, line),
+            &[YES, NO, AUTO],
+        )),
+    }
+}
+
+pub(crate) fn yes_no(value: bool) -> &'static str {
+    if value { "Yes" } else { "No" }
+}
+
+fn column(derived: bool, value: bool) -> String {
+    if derived { AUTO } else { yes_no(value) }.to_owned()
 }
 
 fn parse_yes_no<T>(field: &str, yes: T, no: T) -> Option<T> {
@@ -193,31 +679,53 @@ fn parse_yes_no<T>(field: &str, yes: T, no: T) -> Option<T> {
 #[cfg(test)]
 mod tests {
     use super::*; // Import all functions and types from the parent module
+    use crate::testing::TempDir;
 
     #[test]
     fn test_parse_combination() {
         assert_eq!(
-            parse_combination("1-1-2-step_back-2; Long;  Yes;  No;  No; https://example.com")
-                .unwrap()
-                .as_ref(),
-            &Combination {
+            parse_combination(
+                "1-1-2-step_back-2; Long;  Yes;  No;  No; https://example.com",
+                "basics"
+            )
+                .unwrap(),
+            Combination {
                 description: "1-1-2-step_back-2".to_owned(),
                 distance: Distance::Long,
                 defense: Defense::Yes,
                 faint: Faint::No,
                 body: Body::No,
                 url: Some("https://example.com".to_owned()),
+                library: "basics".to_owned(),
+                source: PathBuf::new(),
+                difficulty: None,
+                tags: vec![],
+                notes: None,
+                stance: None,
+                comments: vec![],
+                derived: Derived::default(),
+                raw: String::new(),
             }
         );
     }
 
+    #[test]
+    fn test_parse_tags() {
+        let combination = parse_combination("1-2; Long; No; No; No; ; Counter, power shot", "").unwrap();
+        assert_eq!(combination.url, None);
+        assert_eq!(combination.tags, vec!["counter", "power_shot"]);
+        assert_eq!(combination.to_line(), "1-2; Long; No; No; No;; counter, power_shot");
+        assert_eq!(combination.all_tags(), vec!["counter", "power_shot", "long"]);
+        assert_eq!(parse_combination(&combination.to_line(), "").unwrap(), combination);
+    }
+
     #[test]
     fn test_parse_error_five_elements() {
         assert_eq!(
-            parse_combination("1-1-2-step_back-2; Long;  Yes")
+            parse_combination("1-1-2-step_back-2; Long;  Yes", "")
                 .unwrap_err()
                 .to_string(),
-            "Parse error: Expect five elements delimited by ; in \"1-1-2-step_back-2; Long;  Yes\""
+            "Expect 6 or 7 elements delimited by ; in \"1-1-2-step_back-2; Long;  Yes\""
                 .to_owned()
         );
     }
@@ -225,10 +733,10 @@ mod tests {
     #[test]
     fn test_parse_error_distance() {
         assert_eq!(
-            parse_combination("1-1-2-step_back-2; XXX;  Yes;  No;  No;")
+            parse_combination("1-1-2-step_back-2; XXX;  Yes;  No;  No;", "")
                 .unwrap_err()
                 .to_string(),
-            "Parse error: Unknown distance \"XXX\" in \"1-1-2-step_back-2; XXX;  Yes;  No;  No;\""
+            "field 2: Unknown distance \"XXX\" in \"1-1-2-step_back-2; XXX;  Yes;  No;  No;\" (expected long|short)"
                 .to_owned()
         );
     }
@@ -236,10 +744,10 @@ mod tests {
     #[test]
     fn test_parse_error_defence() {
         assert_eq!(
-            parse_combination("1-1-2-step_back-2; Long;  XXX;  No;  No;")
+            parse_combination("1-1-2-step_back-2; Long;  XXX;  No;  No;", "")
                 .unwrap_err()
                 .to_string(),
-            "Parse error: Unknown defense \"  XXX\" in \"1-1-2-step_back-2; Long;  XXX;  No;  No;\""
+            "field 3: Unknown defense \"  XXX\" in \"1-1-2-step_back-2; Long;  XXX;  No;  No;\" (expected yes|no|auto)"
                 .to_owned()
         );
     }
@@ -247,10 +755,10 @@ mod tests {
     #[test]
     fn test_parse_error_faint() {
         assert_eq!(
-            parse_combination("1-1-2-step_back-2; Long;  Yes;  XXX;  No;")
+            parse_combination("1-1-2-step_back-2; Long;  Yes;  XXX;  No;", "")
                 .unwrap_err()
                 .to_string(),
-            "Parse error: Unknown faint \"  XXX\" in \"1-1-2-step_back-2; Long;  Yes;  XXX;  No;\""
+            "field 4: Unknown faint \"  XXX\" in \"1-1-2-step_back-2; Long;  Yes;  XXX;  No;\" (expected yes|no|auto)"
                 .to_owned()
         );
     }
@@ -258,17 +766,131 @@ mod tests {
     #[test]
     fn test_parse_error_body() {
         assert_eq!(
-            parse_combination("1-1-2-step_back-2; Long;  Yes;  No;  XXX;")
+            parse_combination("1-1-2-step_back-2; Long;  Yes;  No;  XXX;", "")
+                .unwrap_err()
+                .to_string(),
+            "field 5: Unknown body \"  XXX\" in \"1-1-2-step_back-2; Long;  Yes;  No;  XXX;\" (expected yes|no|auto)"
+                .to_owned()
+        );
+    }
+
+    #[test]
+    fn test_parse_auto_flags() {
+        let combination = parse_combination("f1-2b-slip-3; Long;  auto;  Auto;  ;", "").unwrap();
+        assert_eq!(combination.defense, Defense::Yes);
+        assert_eq!(combination.faint, Faint::Yes);
+        assert_eq!(combination.body, Body::Yes);
+        assert!(combination.inconsistencies().is_empty());
+        assert_eq!(combination.to_line(), "f1-2b-slip-3; Long; auto; auto; auto;");
+    }
+
+    #[test]
+    fn test_parse_error_auto() {
+        assert_eq!(
+            parse_combination("1-9; Long;  auto;  No;  No;", "")
                 .unwrap_err()
                 .to_string(),
-            "Parse error: Unknown body \"  XXX\" in \"1-1-2-step_back-2; Long;  Yes;  No;  XXX;\""
+            "field 3: Cannot derive defense (Invalid punch \"9\", expected 1-6 with optional f prefix and b suffix) in \"1-9; Long;  auto;  No;  No;\""
                 .to_owned()
         );
     }
 
+    #[test]
+    fn test_inconsistencies() {
+        let combination = parse_combination("1-2-3b; Long;  Yes;  No;  No;", "").unwrap();
+        let messages: Vec<String> = combination
+            .inconsistencies()
+            .iter()
+            .map(|e| e.to_string())
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                "field 3: Declared defense Yes but notation says No in \"1-2-3b\"".to_owned(),
+                "field 5: Declared body No but notation says Yes in \"1-2-3b\"".to_owned(),
+            ]
+        );
+    }
+
+    const WARN: LoadOptions = LoadOptions {
+        consistency: Consistency::Warn,
+        skip_invalid: false,
+    };
+
     #[test]
     fn test_load_data() {
         const PATH: &str = "./combinations.txt";
-        assert!(load_data(PATH).is_ok());
+        let loaded = load_data(Path::new(PATH), WARN).unwrap();
+        assert!(loaded.data.iter().all(|c| c.library == "combinations"));
+        assert!(loaded.data.iter().all(|c| c.source == Path::new(PATH)));
+        assert!(loaded.errors.is_empty());
+        assert!(!loaded.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_load_data_errors() {
+        let dir = TempDir::new("load_data_errors");
+        let path = dir.join("errors.txt");
+        std::fs::write(
+            &path,
+            "# comment\n1-2; Long; No; No; No;\n1-2; XXX; No; No; No;\n\n1-2b; Long; No; No; No;\n1-2; Long\n",
+        )
+        .unwrap();
+        let errors = match load_data(&path, WARN) {
+            Err(CombinationError::Errors(errors)) => errors,
+            other => panic!("Unexpected {:?}", other),
+        };
+        let lines: Vec<Option<usize>> = errors.iter().map(|e| e.line).collect();
+        assert_eq!(lines, vec![Some(3), Some(6)]);
+        assert_eq!(errors[0].field, Some(2));
+        assert_eq!(errors[0].file.as_deref(), Some(path.as_path()));
+
+        let strict = LoadOptions {
+            consistency: Consistency::Strict,
+            skip_invalid: true,
+        };
+        let loaded = load_data(&path, strict).unwrap();
+        assert_eq!(loaded.data.len(), 1);
+        let lines: Vec<Option<usize>> = loaded.errors.iter().map(|e| e.line).collect();
+        assert_eq!(lines, vec![Some(3), Some(5), Some(6)]);
+    }
+
+    #[test]
+    fn test_load_structured() {
+        let dir = TempDir::new("load_structured");
+        let path = dir.join("structured.toml");
+        std::fs::write(
+            &path,
+            "[[combination]]\ndescription = \"1-2b\"\ndistance = \"short\"\ndifficulty = 2\n\n[[combination]]\ndescription = \"1-2\"\ndistance = \"long\"\nbody = true\n",
+        )
+        .unwrap();
+        let loaded = load_data(&path, WARN).unwrap();
+        assert_eq!(loaded.data.len(), 2);
+        assert_eq!(loaded.warnings.len(), 1);
+        assert_eq!(loaded.warnings[0].field, Some(BODY_FIELD));
+        assert_eq!(loaded.data[0].body, Body::Yes);
+        assert_eq!(loaded.data[0].library, "structured");
+
+        let strict = LoadOptions {
+            consistency: Consistency::Strict,
+            skip_invalid: true,
+        };
+        let loaded = load_data(&path, strict).unwrap();
+        assert_eq!(loaded.data.len(), 1);
+        assert_eq!(loaded.errors[0].field, Some(BODY_FIELD));
+    }
+
+    #[test]
+    fn test_load_libraries() {
+        let path = PathBuf::from("./combinations.txt");
+        let double = load_libraries(&[path.clone(), path.clone()], WARN).unwrap();
+        let single = load_libraries(&[path], WARN).unwrap();
+        assert_eq!(double.data.len(), 2 * single.data.len());
+        assert!(
+            load_libraries(&[PathBuf::from("./missing.txt")], WARN)
+                .unwrap_err()
+                .to_string()
+                .starts_with("I/O error: ./missing.txt: ")
+        );
     }
 }
@@ -0,0 +1,234 @@
+use std::{
+    env,
+    ffi::OsString,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde::{Serialize, de::DeserializeOwned};
+
+const DATA_KEY: &str = "BOXING_TRAINER_DATA";
+const CONFIG_DIR_KEY: &str = "BOXING_TRAINER_CONFIG_DIR";
+const XDG_CONFIG_HOME_KEY: &str = "XDG_CONFIG_HOME";
+const HOME_KEY: &str = "HOME";
+const APP_DIR: &str = "boxing-trainer";
+const CONFIG_FILE: &str = "boxing-trainer.conf";
+const DEFAULT_LIBRARY: &str = "./combinations.txt";
+const COMMENT: &str = "#";
+const ASSIGN: &str = "=";
+const LIBRARY: &str = "library";
+const SEED_ARG: &str = "--seed";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Config {
+    pub libraries: Vec<PathBuf>,
+    pub seed: Option<u64>,
+}
+
+impl Config {
+    // Libraries come from the first source that names any: command line
+    // arguments, the BOXING_TRAINER_DATA path list, "library = ..." lines
+    // of boxing-trainer.conf in config_dir() and finally ./combinations.txt.
+    pub fn load() -> Config {
+        let args: Vec<String> = env::args().skip(1).collect();
+        let file = fs::read_to_string(config_dir().join(CONFIG_FILE)).ok();
+        Config::from_sources(&args, env::var_os(DATA_KEY), file.as_deref())
+    }
+
+    fn from_sources(args: &[String], data: Option<OsString>, file: Option<&str>) -> Config {
+        let (seed, args) = parse_seed(args);
+        let mut libraries: Vec<PathBuf> = args.iter().map(PathBuf::from).collect();
+        if libraries.is_empty()
+            && let Some(data) = data
+        {
+            libraries = env::split_paths(&data)
+                .filter(|p| !p.as_os_str().is_empty())
+                .collect();
+        }
+        if libraries.is_empty()
+            && let Some(file) = file
+        {
+            libraries = parse_file(file);
+        }
+        if libraries.is_empty() {
+            libraries.push(PathBuf::from(DEFAULT_LIBRARY));
+        }
+        Config { libraries, seed }
+    }
+}
+
+// "--seed N" or "--seed=N" replays the shuffle of an earlier session.
+fn parse_seed(args: &[String]) -> (Option<u64>, Vec<&String>) {
+    let mut seed = None;
+    let mut rest = vec![];
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let value = if arg == SEED_ARG {
+            args.next().map(String::as_str)
+        } else if let Some(value) = arg
+            .strip_prefix(SEED_ARG)
+            .and_then(|v| v.strip_prefix(ASSIGN))
+        {
+            Some(value)
+        } else {
+            rest.push(arg);
+            continue;
+        };
+        match value.map(str::parse) {
+            Some(Ok(value)) => seed = Some(value),
+            _ => eprintln!(
+                "WARNING Expect a number after {}, got {:?}",
+                SEED_ARG,
+                value.unwrap_or_default()
+            ),
+        }
+    }
+    (seed, rest)
+}
+
+// Where the config file, bindings, presets and state are kept:
+// BOXING_TRAINER_CONFIG_DIR, else $XDG_CONFIG_HOME/boxing-trainer, else
+// ~/.config/boxing-trainer.
+pub fn config_dir() -> PathBuf {
+    config_dir_from(
+        env::var_os(CONFIG_DIR_KEY),
+        env::var_os(XDG_CONFIG_HOME_KEY),
+        env::var_os(HOME_KEY),
+    )
+}
+
+fn config_dir_from(dir: Option<OsString>, xdg: Option<OsString>, home: Option<OsString>) -> PathBuf {
+    let non_empty = |value: Option<OsString>| value.filter(|v| !v.is_empty()).map(PathBuf::from);
+    if let Some(dir) = non_empty(dir) {
+        dir
+    } else if let Some(xdg) = non_empty(xdg) {
+        xdg.join(APP_DIR)
+    } else if let Some(home) = non_empty(home) {
+        home.join(".config").join(APP_DIR)
+    } else {
+        PathBuf::from(".")
+    }
+}
+
+// Presets and state are TOML files in config_dir(), a missing file gives
+// the defaults.
+pub fn load_toml<T: DeserializeOwned + Default>(path: &Path) -> io::Result<T> {
+    match fs::read_to_string(path) {
+        Ok(content) => toml::from_str(&content).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}: {}", path.display(), e),
+            )
+        }),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(T::default()),
+        Err(e) => Err(e),
+    }
+}
+
+pub fn save_toml<T: Serialize>(path: &Path, value: &T) -> io::Result<()> {
+    let content = toml::to_string(value)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, content)
+}
+
+fn parse_file(content: &str) -> Vec<PathBuf> {
+    let mut libraries = vec![];
+    for line in content.lines() {
+        let line = line.trim();
+        if line.starts_with(COMMENT) || line.is_empty() {
+            continue;
+        }
+        match line.split_once(ASSIGN) {
+            Some((key, value)) if key.trim().eq_ignore_ascii_case(LIBRARY) => {
+                libraries.push(PathBuf::from(value.trim()));
+            }
+            _ => eprintln!("WARNING Unknown setting {:?} in {}", line, CONFIG_FILE),
+        }
+    }
+    libraries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default() {
+        assert_eq!(
+            Config::from_sources(&[], None, None).libraries,
+            vec![PathBuf::from(DEFAULT_LIBRARY)]
+        );
+    }
+
+    #[test]
+    fn test_args_win() {
+        let config = Config::from_sources(
+            &["southpaw.txt".to_owned(), "petr.txt".to_owned()],
+            Some(OsString::from("env.txt")),
+            Some("library = file.txt"),
+        );
+        assert_eq!(
+            config.libraries,
+            vec![PathBuf::from("southpaw.txt"), PathBuf::from("petr.txt")]
+        );
+    }
+
+    #[test]
+    fn test_env() {
+        let data = env::join_paths(["a.txt", "b.txt"]).unwrap();
+        let config = Config::from_sources(&[], Some(data), Some("library = file.txt"));
+        assert_eq!(
+            config.libraries,
+            vec![PathBuf::from("a.txt"), PathBuf::from("b.txt")]
+        );
+    }
+
+    #[test]
+    fn test_file() {
+        let config = Config::from_sources(
+            &[],
+            None,
+            Some("# libraries\nlibrary = basics.txt\nLibrary=  southpaw.txt \ncolor = red\n"),
+        );
+        assert_eq!(
+            config.libraries,
+            vec![PathBuf::from("basics.txt"), PathBuf::from("southpaw.txt")]
+        );
+    }
+
+    #[test]
+    fn test_seed() {
+        let args: Vec<String> = ["--seed", "42", "basics.txt", "--seed=x", "southpaw.txt"]
+            .iter()
+            .map(|a| a.to_string())
+            .collect();
+        let config = Config::from_sources(&args, None, None);
+        assert_eq!(config.seed, Some(42));
+        assert_eq!(
+            config.libraries,
+            vec![PathBuf::from("basics.txt"), PathBuf::from("southpaw.txt")]
+        );
+        let config = Config::from_sources(&["--seed=7".to_owned()], None, None);
+        assert_eq!(config.seed, Some(7));
+        assert_eq!(config.libraries, vec![PathBuf::from(DEFAULT_LIBRARY)]);
+        assert_eq!(Config::from_sources(&[], None, None).seed, None);
+    }
+
+    #[test]
+    fn test_config_dir() {
+        let os = |s: &str| Some(OsString::from(s));
+        assert_eq!(config_dir_from(os("/c"), os("/x"), os("/h")), PathBuf::from("/c"));
+        assert_eq!(
+            config_dir_from(None, os("/x"), os("/h")),
+            PathBuf::from("/x/boxing-trainer")
+        );
+        assert_eq!(
+            config_dir_from(os(""), os(""), os("/h")),
+            PathBuf::from("/h/.config/boxing-trainer")
+        );
+        assert_eq!(config_dir_from(None, None, None), PathBuf::from("."));
+    }
+}
@@ -0,0 +1,808 @@
+use std::{
+    fmt, fs,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use toml_edit::{ArrayOfTables, DocumentMut, Item, Table, Value};
+
+use crate::combination::{
+    self, Combination, Consistency, DELIMITER, Distance, LoadOptions, Loaded, ParseError,
+};
+use crate::format::{self, Format, Record};
+use crate::tags;
+
+// The keys of the structured formats, see format::Document and format::Record.
+const COMBINATION_KEY: &str = "combination";
+const COMMENTS_KEY: &str = "comments";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flag {
+    Auto,
+    Yes,
+    No,
+}
+
+pub const FLAGS: [Flag; 3] = [Flag::Auto, Flag::Yes, Flag::No];
+
+// Where a saved combination goes in its library file.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Target {
+    Replace(Rc<Combination>),
+    InsertAfter(Option<Rc<Combination>>),
+}
+
+#[derive(Debug, Clone)]
+pub struct Editor {
+    record: Record,
+    // Tags as typed, split when validated.
+    tags: String,
+    library: PathBuf,
+    target: Target,
+}
+
+impl Editor {
+    pub fn new(library: PathBuf) -> Editor {
+        Editor {
+            record: Record {
+                description: String::new(),
+                distance: Distance::Long,
+                defense: None,
+                faint: None,
+                body: None,
+                url: None,
+                difficulty: None,
+                tags: vec![],
+                notes: None,
+                stance: None,
+                comments: vec![],
+            },
+            tags: String::new(),
+            library,
+            target: Target::InsertAfter(None),
+        }
+    }
+
+    pub fn edit(library: PathBuf, combination: Rc<Combination>) -> Editor {
+        let record = Record::from(combination.as_ref());
+        Editor {
+            tags: record.tags.join(", "),
+            record,
+            library,
+            target: Target::Replace(combination),
+        }
+    }
+
+    // The copy is inserted right after the original without its comments.
+    pub fn duplicate(library: PathBuf, combination: Rc<Combination>) -> Editor {
+        let mut record = Record::from(combination.as_ref());
+        record.comments.clear();
+        Editor {
+            tags: record.tags.join(", "),
+            record,
+            library,
+            target: Target::InsertAfter(Some(combination)),
+        }
+    }
+
+    // Generated combinations are in no file yet, saving appends them.
+    pub fn add(library: PathBuf, combination: &Combination) -> Editor {
+        let record = Record::from(combination);
+        Editor {
+            tags: record.tags.join(", "),
+            record,
+            library,
+            target: Target::InsertAfter(None),
+        }
+    }
+
+    pub fn is_new(&self) -> bool {
+        matches!(self.target, Target::InsertAfter(_))
+    }
+
+    pub fn library(&self) -> &Path {
+        &self.library
+    }
+
+    pub fn set_library(&mut self, library: PathBuf) {
+        if self.library != library {
+            self.library = library;
+            self.target = Target::InsertAfter(None);
+        }
+    }
+
+    pub fn description(&self) -> &str {
+        &self.record.description
+    }
+
+    pub fn set_description(&mut self, description: String) {
+        self.record.description = description;
+    }
+
+    pub fn distance(&self) -> Distance {
+        self.record.distance
+    }
+
+    pub fn set_distance(&mut self, distance: Distance) {
+        self.record.distance = distance;
+    }
+
+    pub fn defense(&self) -> Flag {
+        self.record.defense.into()
+    }
+
+    pub fn set_defense(&mut self, flag: Flag) {
+        self.record.defense = flag.into();
+    }
+
+    pub fn faint(&self) -> Flag {
+        self.record.faint.into()
+    }
+
+    pub fn set_faint(&mut self, flag: Flag) {
+        self.record.faint = flag.into();
+    }
+
+    pub fn body(&self) -> Flag {
+        self.record.body.into()
+    }
+
+    pub fn set_body(&mut self, flag: Flag) {
+        self.record.body = flag.into();
+    }
+
+    pub fn tags(&self) -> &str {
+        &self.tags
+    }
+
+    pub fn set_tags(&mut self, tags: String) {
+        self.tags = tags;
+    }
+
+    pub fn url(&self) -> &str {
+        self.record.url.as_deref().unwrap_or_default()
+    }
+
+    pub fn set_url(&mut self, url: String) {
+        let url = url.trim().to_owned();
+        self.record.url = if url.is_empty() { None } else { Some(url) };
+    }
+
+    pub fn validate(&self) -> Result<Combination, ParseError> {
+        let mut record = self.record.clone();
+        record.description = record.description.trim().to_owned();
+        record.tags = tags::parse(&self.tags);
+        if record.description.is_empty() {
+            return Err(ParseError {
+                message: "Description is empty".to_owned(),
+                ..ParseError::default()
+            });
+        }
+        // The delimiter would split the line into extra columns.
+        if Format::from_path(&self.library) == Format::Legacy
+            && (record.description.contains(DELIMITER)
+                || record
+                    .url
+                    .as_deref()
+                    .unwrap_or_default()
+                    .contains(DELIMITER)
+                || record.tags.iter().any(|tag| tag.contains(DELIMITER)))
+        {
+            return Err(ParseError {
+                message: format!(
+                    "{:?} is not allowed in {}",
+                    DELIMITER,
+                    self.library.display()
+                ),
+                ..ParseError::default()
+            });
+        }
+        let combination = record.into_combination(&combination::library_name(&self.library))?;
+        Ok(Combination {
+            source: self.library.clone(),
+            ..combination
+        })
+    }
+
+    // Changes the combination in the library file, the other combinations and
+    // the comments stay as written. Returns the combination as read back.
+    pub fn save(&self) -> Result<Rc<Combination>, String> {
+        let combination = Rc::new(self.validate().map_err(|e| e.to_string())?);
+        let data = load(&self.library)?;
+        let (index, replace) = match &self.target {
+            Target::Replace(original) => (position(&data.data, original, &self.library)?, true),
+            Target::InsertAfter(Some(original)) => {
+                (position(&data.data, original, &self.library)? + 1, false)
+            }
+            Target::InsertAfter(None) => (data.data.len(), false),
+        };
+        let mut file = open(&self.library, data)?;
+        if replace {
+            file.replace(index, &combination);
+        } else {
+            file.insert(index, &combination);
+        }
+        file.write(&self.library)?;
+        Ok(load(&self.library)?.data[index].clone())
+    }
+
+    pub fn delete(&self) -> Result<(), String> {
+        let Target::Replace(original) = &self.target else {
+            return Ok(());
+        };
+        let data = load(&self.library)?;
+        let index = position(&data.data, original, &self.library)?;
+        let mut file = open(&self.library, data)?;
+        file.remove(index);
+        file.write(&self.library)
+    }
+}
+
+// Appends the combinations to the library in a single write, nothing is
+// saved if any of them is invalid there.
+pub fn append(library: &Path, combinations: &[Rc<Combination>]) -> Result<(), String> {
+    let mut added = vec![];
+    for combination in combinations {
+        let editor = Editor::add(library.to_owned(), combination);
+        added.push(Rc::new(editor.validate().map_err(|e| e.to_string())?));
+    }
+    let data = load(library)?;
+    let count = data.data.len();
+    let mut file = open(library, data)?;
+    for (index, combination) in (count..).zip(&added) {
+        file.insert(index, combination);
+    }
+    file.write(library)
+}
+
+// A missing library starts out empty. Libraries with invalid entries are not
+// rewritten, they would be lost.
+fn load(path: &Path) -> Result<Loaded, String> {
+    if !path.exists() {
+        return Ok(Loaded::default());
+    }
+    let options = LoadOptions {
+        consistency: Consistency::Warn,
+        skip_invalid: false,
+    };
+    combination::load_data(path, options).map_err(|e| e.messages().join("\n"))
+}
+
+fn position(
+    data: &[Rc<Combination>],
+    combination: &Combination,
+    path: &Path,
+) -> Result<usize, String> {
+    data.iter()
+        .position(|c| c.as_ref() == combination)
+        .ok_or(format!(
+            "{:?} changed in {}, reload first",
+            combination.description,
+            path.display()
+        ))
+}
+
+fn read(path: &Path) -> Result<String, String> {
+    if !path.exists() {
+        return Ok(String::new());
+    }
+    fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+// A library file as written. Combinations are addressed by the index they
+// are loaded with, everything else is kept as it is.
+trait LibraryFile {
+    fn replace(&mut self, index: usize, combination: &Rc<Combination>);
+    // Right below the combination before index.
+    fn insert(&mut self, index: usize, combination: &Rc<Combination>);
+    // Comments above the removed combination stay where they were.
+    fn remove(&mut self, index: usize);
+    fn write(&self, path: &Path) -> Result<(), String>;
+}
+
+fn open(path: &Path, data: Loaded) -> Result<Box<dyn LibraryFile>, String> {
+    Ok(match Format::from_path(path) {
+        Format::Legacy => Box::new(LegacyFile::read(path)?),
+        Format::Toml => Box::new(TomlFile::read(path)?),
+        _ => Box::new(JsonFile::read(path, data)?),
+    })
+}
+
+// The lines of a legacy file as written, so that saving keeps the spacing,
+// auto columns and blank lines of the combinations that were not edited.
+struct LegacyFile {
+    lines: Vec<String>,
+    newline: &'static str,
+}
+
+impl LegacyFile {
+    fn read(path: &Path) -> Result<LegacyFile, String> {
+        let content = read(path)?;
+        Ok(LegacyFile {
+            lines: content.lines().map(str::to_owned).collect(),
+            newline: if content.contains("\r\n") {
+                "\r\n"
+            } else {
+                "\n"
+            },
+        })
+    }
+
+    // The line of each combination, in the order they are loaded.
+    fn combination_lines(&self) -> Vec<usize> {
+        (0..self.lines.len())
+            .filter(|index| combination::is_legacy_combination(&self.lines[*index]))
+            .collect()
+    }
+}
+
+impl LibraryFile for LegacyFile {
+    fn replace(&mut self, index: usize, combination: &Rc<Combination>) {
+        let line = self.combination_lines()[index];
+        self.lines[line] = combination.to_line();
+    }
+
+    fn insert(&mut self, index: usize, combination: &Rc<Combination>) {
+        let line = match index.checked_sub(1) {
+            Some(previous) => self.combination_lines()[previous] + 1,
+            None => 0,
+        };
+        let text = combination::write_legacy(&Loaded {
+            data: vec![combination.clone()],
+            ..Loaded::default()
+        });
+        self.lines
+            .splice(line..line, text.lines().map(str::to_owned));
+    }
+
+    fn remove(&mut self, index: usize) {
+        let line = self.combination_lines()[index];
+        self.lines.remove(line);
+    }
+
+    fn write(&self, path: &Path) -> Result<(), String> {
+        let mut content = self.lines.join(self.newline);
+        if !content.is_empty() {
+            content.push_str(self.newline);
+        }
+        fs::write(path, content).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+}
+
+// The parsed TOML document, which keeps the layout and the # comments of the
+// tables that are not edited.
+struct TomlFile {
+    document: DocumentMut,
+}
+
+impl TomlFile {
+    fn read(path: &Path) -> Result<TomlFile, String> {
+        let mut document: DocumentMut = read(path)?
+            .parse()
+            .map_err(|e| format!("{}: {}", path.display(), e))?;
+        let combinations = document
+            .entry(COMBINATION_KEY)
+            .or_insert(Item::ArrayOfTables(ArrayOfTables::new()));
+        if !combinations.is_array_of_tables() {
+            return Err(format!(
+                "{}: saving would rewrite {:?}, write it as [[{}]] tables",
+                path.display(),
+                COMBINATION_KEY,
+                COMBINATION_KEY
+            ));
+        }
+        Ok(TomlFile { document })
+    }
+
+    fn tables(&mut self) -> &mut ArrayOfTables {
+        self.document[COMBINATION_KEY]
+            .as_array_of_tables_mut()
+            .expect("Checked when read")
+    }
+
+    fn table(combination: &Combination) -> Table {
+        toml_edit::ser::to_document(&Record::from(combination))
+            .expect("Records are TOML tables")
+            .as_table()
+            .clone()
+    }
+}
+
+impl LibraryFile for TomlFile {
+    // The table keeps its place and the comments above it.
+    fn replace(&mut self, index: usize, combination: &Rc<Combination>) {
+        let Some(table) = self.tables().get_mut(index) else {
+            return;
+        };
+        let mut edited = TomlFile::table(combination);
+        edited.set_position(table.position().unwrap_or_default());
+        *edited.decor_mut() = table.decor().clone();
+        *table = edited;
+    }
+
+    fn insert(&mut self, index: usize, combination: &Rc<Combination>) {
+        let tables = self.tables();
+        let mut all: Vec<Table> = tables.iter().cloned().collect();
+        all.insert(index, TomlFile::table(combination));
+        tables.clear();
+        for table in all {
+            tables.push(table);
+        }
+    }
+
+    fn remove(&mut self, index: usize) {
+        let tables = self.tables();
+        let Some(removed) = tables.get(index).cloned() else {
+            return;
+        };
+        tables.remove(index);
+        let comments: String = prefix(&removed)
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| format!("{}\n", line))
+            .collect();
+        match tables.get_mut(index) {
+            Some(next) => {
+                let prefix = prefix(next) + &comments;
+                next.decor_mut().set_prefix(prefix);
+                prepend_comments(next, &removed);
+            }
+            None => {
+                let trailing = self.document.trailing().as_str().unwrap_or_default();
+                let trailing = comments + trailing;
+                self.document.set_trailing(trailing);
+                prepend_comments(self.document.as_table_mut(), &removed);
+            }
+        }
+    }
+
+    fn write(&self, path: &Path) -> Result<(), String> {
+        fs::write(path, self.document.to_string()).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+}
+
+// The blank lines and # comments above the header of the table.
+fn prefix(table: &Table) -> String {
+    let prefix = table.decor().prefix().and_then(|prefix| prefix.as_str());
+    prefix.unwrap_or_default().to_owned()
+}
+
+// Moves the comments field of a removed record to the next one or to the
+// document, see format::Record::comments.
+fn prepend_comments(table: &mut Table, removed: &Table) {
+    let Some(Item::Value(Value::Array(removed))) = removed.get(COMMENTS_KEY) else {
+        return;
+    };
+    let mut comments = removed.clone();
+    if let Some(Item::Value(Value::Array(existing))) = table.get(COMMENTS_KEY) {
+        comments.extend(existing.iter().cloned());
+    }
+    table.insert(COMMENTS_KEY, Item::Value(Value::Array(comments)));
+}
+
+// JSON has no comments, the document is written as a whole. Files laid out
+// differently than it would be written are not saved, all of them would change.
+struct JsonFile {
+    loaded: Loaded,
+}
+
+impl JsonFile {
+    fn read(path: &Path, loaded: Loaded) -> Result<JsonFile, String> {
+        let content = read(path)?;
+        if !content.is_empty() && format::write(Format::Json, &loaded)? != content {
+            return Err(format!(
+                "{}: saving would rewrite the whole file, convert it to JSON first",
+                path.display()
+            ));
+        }
+        Ok(JsonFile { loaded })
+    }
+}
+
+impl LibraryFile for JsonFile {
+    fn replace(&mut self, index: usize, combination: &Rc<Combination>) {
+        self.loaded.data[index] = combination.clone();
+    }
+
+    fn insert(&mut self, index: usize, combination: &Rc<Combination>) {
+        self.loaded.data.insert(index, combination.clone());
+    }
+
+    fn remove(&mut self, index: usize) {
+        let data = &mut self.loaded.data;
+        let removed = data.remove(index);
+        let mut comments = removed.comments.clone();
+        match data.get_mut(index) {
+            Some(next) => {
+                comments.extend(next.comments.iter().cloned());
+                *next = Rc::new(Combination {
+                    comments,
+                    ..next.as_ref().clone()
+                });
+            }
+            None => {
+                comments.append(&mut self.loaded.comments);
+                self.loaded.comments = comments;
+            }
+        }
+    }
+
+    fn write(&self, path: &Path) -> Result<(), String> {
+        let content = format::write(Format::Json, &self.loaded)?;
+        fs::write(path, content).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+}
+
+impl From<Option<bool>> for Flag {
+    fn from(value: Option<bool>) -> Self {
+        match value {
+            None => Flag::Auto,
+            Some(true) => Flag::Yes,
+            Some(false) => Flag::No,
+        }
+    }
+}
+
+impl From<Flag> for Option<bool> {
+    fn from(flag: Flag) -> Self {
+        match flag {
+            Flag::Auto => None,
+            Flag::Yes => Some(true),
+            Flag::No => Some(false),
+        }
+    }
+}
+
+impl fmt::Display for Flag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Flag::Auto => write!(f, "Auto"),
+            Flag::Yes => write!(f, "Yes"),
+            Flag::No => write!(f, "No"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::combination::{Body, Defense};
+    use crate::testing::TempDir;
+
+    const CONTENT: &str =
+        "# basics\n1-2; Long; No; No; No;\n# body\n1-2b; Long; No; No; Yes;\n# end\n";
+
+    fn library(dir: &TempDir) -> PathBuf {
+        let path = dir.join("basics.txt");
+        fs::write(&path, CONTENT).unwrap();
+        path
+    }
+
+    fn first(path: &Path) -> Rc<Combination> {
+        load(path).unwrap().data[0].clone()
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut editor = Editor::new(PathBuf::from("basics.txt"));
+        assert_eq!(
+            editor.validate().unwrap_err().message,
+            "Description is empty"
+        );
+        editor.set_description(" 1-2-3b ".to_owned());
+        let combination = editor.validate().unwrap();
+        assert_eq!(combination.description, "1-2-3b");
+        assert_eq!(combination.body, Body::Yes);
+        assert_eq!(combination.library, "basics");
+        assert_eq!(combination.source, PathBuf::from("basics.txt"));
+        editor.set_body(Flag::No);
+        assert_eq!(editor.validate().unwrap().inconsistencies().len(), 1);
+        editor.set_description("1-9".to_owned());
+        assert!(editor.validate().is_err());
+        editor.set_description("1;2".to_owned());
+        assert!(
+            editor
+                .validate()
+                .unwrap_err()
+                .message
+                .contains("not allowed")
+        );
+    }
+
+    #[test]
+    fn test_save_and_delete() {
+        let dir = TempDir::new("save_and_delete");
+        let path = library(&dir);
+        let mut editor = Editor::edit(path.clone(), first(&path));
+        editor.set_description("1-1-2".to_owned());
+        editor.save().unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "# basics\n1-1-2; Long; No; No; No;\n# body\n1-2b; Long; No; No; Yes;\n# end\n"
+        );
+        assert!(editor.save().unwrap_err().contains("reload first"));
+
+        let editor = Editor::duplicate(path.clone(), first(&path));
+        editor.save().unwrap();
+        let mut editor = Editor::new(path.clone());
+        editor.set_description("3".to_owned());
+        editor.set_url("https://example.com".to_owned());
+        editor.save().unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "# basics\n1-1-2; Long; No; No; No;\n1-1-2; Long; No; No; No;\n# body\n1-2b; Long; No; No; Yes;\n3; Long; auto; auto; auto; https://example.com\n# end\n"
+        );
+
+        Editor::edit(path.clone(), first(&path)).delete().unwrap();
+        let loaded = load(&path).unwrap();
+        assert_eq!(loaded.data.len(), 3);
+        assert_eq!(loaded.data[0].comments, vec!["basics".to_owned()]);
+    }
+
+    #[test]
+    fn test_append() {
+        let dir = TempDir::new("append");
+        let path = library(&dir);
+        let added = vec![
+            Rc::new(Combination::from_description("1-1-2")),
+            Rc::new(Combination::from_description("3-2")),
+        ];
+        append(&path, &added).unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "# basics\n1-2; Long; No; No; No;\n# body\n1-2b; Long; No; No; Yes;\n1-1-2; Long; No; No; No;\n3-2; Long; No; No; No;\n# end\n"
+        );
+        let invalid = vec![
+            Rc::new(Combination::from_description("1-2-3")),
+            Rc::new(Combination::from_description("1;2")),
+        ];
+        assert!(append(&path, &invalid).unwrap_err().contains("not allowed"));
+        assert_eq!(load(&path).unwrap().data.len(), 4);
+    }
+
+    #[test]
+    fn test_save_and_load_columns() {
+        let dir = TempDir::new("save_and_load_columns");
+        let path = dir.join("basics.txt");
+        let mut editor = Editor::new(path.clone());
+        editor.set_description("1-2".to_owned());
+        editor.set_tags("southpaw;counter".to_owned());
+        assert!(
+            editor
+                .validate()
+                .unwrap_err()
+                .message
+                .contains("not allowed")
+        );
+        editor.set_tags("southpaw, counter".to_owned());
+        editor.set_url("https://example.com/?a=1;b=2".to_owned());
+        assert!(
+            editor
+                .validate()
+                .unwrap_err()
+                .message
+                .contains("not allowed")
+        );
+        editor.set_url("https://example.com/?a=1&b=2".to_owned());
+        let saved = editor.save().unwrap();
+        assert_eq!(*first(&path), *saved);
+        assert_eq!(saved.tags, vec!["southpaw", "counter"]);
+    }
+
+    const TOML: &str = r#"# drills
+comments = ["top"]
+
+# first
+[[combination]]
+description = "1-2"
+distance = "long" # jab cross
+
+[[combination]]
+description="1-2b"
+distance="short"
+comments = ["body"]
+
+# last
+[[combination]]
+description = "3"
+distance = "long"
+defense = false
+"#;
+
+    #[test]
+    fn test_save_toml_keeps_comments() {
+        let dir = TempDir::new("save_toml_keeps_comments");
+        let path = dir.join("basics.toml");
+        fs::write(&path, TOML).unwrap();
+        let last = load(&path).unwrap().data[2].clone();
+        let mut editor = Editor::edit(path.clone(), last);
+        editor.set_description("3-2".to_owned());
+        editor.save().unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            TOML.replace("\"3\"", "\"3-2\"")
+        );
+        let second = load(&path).unwrap().data[1].clone();
+        Editor::edit(path.clone(), second).delete().unwrap();
+        let editor = Editor::duplicate(path.clone(), first(&path));
+        editor.save().unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            r#"# drills
+comments = ["top"]
+
+# first
+[[combination]]
+description = "1-2"
+distance = "long" # jab cross
+
+[[combination]]
+description = "1-2"
+distance = "long"
+
+# last
+[[combination]]
+description = "3-2"
+distance = "long"
+defense = false
+comments = ["body"]
+"#
+        );
+    }
+
+    #[test]
+    fn test_save_json_layout() {
+        let dir = TempDir::new("save_json_layout");
+        let path = dir.join("basics.json");
+        fs::write(
+            &path,
+            r#"{"combination": [{"description": "1-2", "distance": "long"}]}"#,
+        )
+        .unwrap();
+        let mut editor = Editor::edit(path.clone(), first(&path));
+        editor.set_description("1-1-2".to_owned());
+        assert!(
+            editor
+                .save()
+                .unwrap_err()
+                .contains("rewrite the whole file")
+        );
+
+        fs::write(
+            &path,
+            format::write(Format::Json, &load(&path).unwrap()).unwrap(),
+        )
+        .unwrap();
+        let mut editor = Editor::edit(path.clone(), first(&path));
+        editor.set_description("1-1-2".to_owned());
+        editor.save().unwrap();
+        assert_eq!(first(&path).description, "1-1-2");
+        assert_eq!(first(&path).defense, Defense::No);
+        assert!(!fs::read_to_string(&path).unwrap().contains("defense"));
+    }
+
+    #[test]
+    fn test_save_keeps_lines() {
+        let dir = TempDir::new("save_keeps_lines");
+        let path = dir.join("basics.txt");
+        fs::write(
+            &path,
+            "1-2;long;no;no;no;\n\n# body\n1-2b ; Long; auto; auto; auto;\n",
+        )
+        .unwrap();
+        let mut editor = Editor::edit(path.clone(), first(&path));
+        editor.set_description("1-1-2".to_owned());
+        editor.save().unwrap();
+        let editor = Editor::duplicate(path.clone(), first(&path));
+        editor.save().unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "1-1-2; Long; No; No; No;\n1-1-2; Long; No; No; No;\n\n# body\n1-2b ; Long; auto; auto; auto;\n"
+        );
+        Editor::edit(path.clone(), first(&path)).delete().unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "1-1-2; Long; No; No; No;\n\n# body\n1-2b ; Long; auto; auto; auto;\n"
+        );
+    }
+}
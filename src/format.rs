@@ -0,0 +1,508 @@
+use std::{
+    fmt, fs,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::combination::{
+    self, Body, Combination, Consistency, Defense, Derived, Distance, Faint, LoadOptions, Loaded,
+    ParseError, Parsed, Stance,
+};
+use crate::notation::{self, Notation};
+use crate::tags;
+
+// CSV and Markdown can only be written, for spreadsheets and documentation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Legacy,
+    Toml,
+    Json,
+    Csv,
+    Markdown,
+}
+
+// One combination as written in the structured formats. Missing
+// defense/faint/body flags are derived from the notation.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Record {
+    pub description: String,
+    pub distance: Distance,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub defense: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub faint: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub difficulty: Option<u8>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stance: Option<Stance>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub comments: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Document {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub comments: Vec<String>,
+    #[serde(default, rename = "combination")]
+    pub combinations: Vec<Record>,
+}
+
+const TOML_EXTENSION: &str = "toml";
+const JSON_EXTENSION: &str = "json";
+const CSV_EXTENSION: &str = "csv";
+const MARKDOWN_EXTENSIONS: [&str; 2] = ["md", "markdown"];
+const MAX_DIFFICULTY: u8 = 5;
+const COLUMNS: [&str; 10] = [
+    "Description",
+    "Distance",
+    "Defense",
+    "Faint",
+    "Body",
+    "Difficulty",
+    "Tags",
+    "Stance",
+    "Notes",
+    "URL",
+];
+
+impl Format {
+    pub fn from_path(path: &Path) -> Format {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some(e) if e.eq_ignore_ascii_case(TOML_EXTENSION) => Format::Toml,
+            Some(e) if e.eq_ignore_ascii_case(JSON_EXTENSION) => Format::Json,
+            Some(e) if e.eq_ignore_ascii_case(CSV_EXTENSION) => Format::Csv,
+            Some(e)
+                if MARKDOWN_EXTENSIONS
+                    .iter()
+                    .any(|m| e.eq_ignore_ascii_case(m)) =>
+            {
+                Format::Markdown
+            }
+            _ => Format::Legacy,
+        }
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Format::Legacy => write!(f, "Legacy"),
+            Format::Toml => write!(f, "TOML"),
+            Format::Json => write!(f, "JSON"),
+            Format::Csv => write!(f, "CSV"),
+            Format::Markdown => write!(f, "Markdown"),
+        }
+    }
+}
+
+impl From<&Combination> for Record {
+    fn from(combination: &Combination) -> Self {
+        Record {
+            description: combination.description.clone(),
+            distance: combination.distance,
+            defense: (!combination.derived.defense).then_some(combination.defense == Defense::Yes),
+            faint: (!combination.derived.faint).then_some(combination.faint == Faint::Yes),
+            body: (!combination.derived.body).then_some(combination.body == Body::Yes),
+            url: combination.url.clone(),
+            difficulty: combination.difficulty,
+            tags: combination.tags.clone(),
+            notes: combination.notes.clone(),
+            stance: combination.stance,
+            comments: combination.comments.clone(),
+        }
+    }
+}
+
+impl Record {
+    pub fn into_combination(self, library: &str) -> Result<Combination, ParseError> {
+        if let Some(difficulty) = self.difficulty
+            && !(1..=MAX_DIFFICULTY).contains(&difficulty)
+        {
+            return Err(record_error(format!(
+                "Difficulty {} of {:?} out of range 1-{}",
+                difficulty, self.description, MAX_DIFFICULTY
+            )));
+        }
+        let notation = notation::parse(&self.description);
+        let derive = |name: &str, declared: Option<bool>, derived: fn(&Notation) -> bool| match (
+            declared, &notation,
+        ) {
+            (Some(value), _) => Ok(value),
+            (None, Ok(notation)) => Ok(derived(notation)),
+            (None, Err(e)) => Err(record_error(format!(
+                "Cannot derive {} ({}) of {:?}",
+                name, e, self.description
+            ))),
+        };
+        let defense = derive("defense", self.defense, Notation::has_defense)?;
+        let faint = derive("faint", self.faint, Notation::has_feint)?;
+        let body = derive("body", self.body, Notation::has_body)?;
+        Ok(Combination {
+            description: self.description,
+            distance: self.distance,
+            defense: if defense { Defense::Yes } else { Defense::No },
+            faint: if faint { Faint::Yes } else { Faint::No },
+            body: if body { Body::Yes } else { Body::No },
+            url: self.url,
+            library: library.to_owned(),
+            source: PathBuf::new(),
+            difficulty: self.difficulty,
+            tags: self
+                .tags
+                .iter()
+                .map(|tag| tags::normalize(tag))
+                .filter(|tag| !tag.is_empty())
+                .collect(),
+            notes: self.notes,
+            stance: self.stance,
+            comments: self.comments,
+            derived: Derived {
+                defense: self.defense.is_none(),
+                faint: self.faint.is_none(),
+                body: self.body.is_none(),
+            },
+            raw: String::new(),
+        })
+    }
+}
+
+fn record_error(message: String) -> ParseError {
+    ParseError {
+        message,
+        ..ParseError::default()
+    }
+}
+
+// Returns the combinations and the comments of the document itself.
+pub fn parse_structured(
+    format: Format,
+    content: &str,
+    library: &str,
+) -> (Vec<Parsed>, Vec<String>) {
+    let document: Result<Document, ParseError> = match format {
+        Format::Toml => toml::from_str(content).map_err(|e| ParseError {
+            line: e.span().map(|span| line_of(content, span.start)),
+            message: e.message().to_owned(),
+            ..ParseError::default()
+        }),
+        Format::Json => serde_json::from_str(content).map_err(|e| ParseError {
+            line: Some(e.line()),
+            message: e.to_string(),
+            ..ParseError::default()
+        }),
+        format => unreachable!("{} files are not structured", format),
+    };
+    match document {
+        Ok(document) => {
+            let parsed = document
+                .combinations
+                .into_iter()
+                .enumerate()
+                .map(|(index, record)| {
+                    record.into_combination(library).map_err(|e| ParseError {
+                        message: format!("combination {}: {}", index + 1, e.message),
+                        ..e
+                    })
+                })
+                .collect();
+            (parsed, document.comments)
+        }
+        Err(e) => (vec![Err(e)], vec![]),
+    }
+}
+
+pub fn write(format: Format, loaded: &Loaded) -> Result<String, String> {
+    let data = &loaded.data;
+    let document = || Document {
+        comments: loaded.comments.clone(),
+        combinations: data.iter().map(|c| Record::from(c.as_ref())).collect(),
+    };
+    match format {
+        Format::Legacy => Ok(combination::write_legacy(loaded)),
+        Format::Toml => toml::to_string(&document()).map_err(|e| e.to_string()),
+        Format::Json => serde_json::to_string_pretty(&document())
+            .map(|json| json + "\n")
+            .map_err(|e| e.to_string()),
+        Format::Csv => Ok(write_csv(data)),
+        Format::Markdown => Ok(write_markdown(data)),
+    }
+}
+
+fn columns(combination: &Combination) -> [String; 10] {
+    [
+        combination.description.clone(),
+        combination.distance.to_string(),
+        combination::yes_no(combination.defense == Defense::Yes).to_owned(),
+        combination::yes_no(combination.faint == Faint::Yes).to_owned(),
+        combination::yes_no(combination.body == Body::Yes).to_owned(),
+        combination
+            .difficulty
+            .map(|d| d.to_string())
+            .unwrap_or_default(),
+        combination.tags.join(", "),
+        combination
+            .stance
+            .map(|s| s.to_string())
+            .unwrap_or_default(),
+        combination.notes.clone().unwrap_or_default(),
+        combination.url.clone().unwrap_or_default(),
+    ]
+}
+
+fn write_csv(data: &[Rc<Combination>]) -> String {
+    let mut content = COLUMNS.join(",") + "\n";
+    for combination in data {
+        let row: Vec<String> = columns(combination).iter().map(|c| csv_field(c)).collect();
+        content.push_str(&row.join(","));
+        content.push('\n');
+    }
+    content
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+fn write_markdown(data: &[Rc<Combination>]) -> String {
+    let mut content = format!("| {} |\n", COLUMNS.join(" | "));
+    content.push_str(&format!("|{}\n", " --- |".repeat(COLUMNS.len())));
+    for combination in data {
+        let mut row = columns(combination);
+        if let Some(url) = &combination.url {
+            row[COLUMNS.len() - 1] = format!("[link]({})", url);
+        }
+        let cells: Vec<String> = row.iter().map(|c| markdown_cell(c)).collect();
+        content.push_str(&format!("| {} |\n", cells.join(" | ")));
+    }
+    content
+}
+
+fn markdown_cell(cell: &str) -> String {
+    cell.replace('|', "\\|").replace('\n', "<br>")
+}
+
+// Usage: boxing-trainer convert INPUT... OUTPUT, the format of every file
+// is taken from its extension. Returns the number of written combinations.
+pub fn convert(args: &[String]) -> Result<usize, String> {
+    let (output, inputs) = match args.split_last() {
+        Some((output, inputs)) if !inputs.is_empty() => (PathBuf::from(output), inputs),
+        _ => return Err("Usage: boxing-trainer convert INPUT... OUTPUT".to_owned()),
+    };
+    let inputs: Vec<PathBuf> = inputs.iter().map(PathBuf::from).collect();
+    let options = LoadOptions {
+        consistency: Consistency::Warn,
+        skip_invalid: false,
+    };
+    let loaded =
+        combination::load_libraries(&inputs, options).map_err(|e| e.messages().join("\n"))?;
+    for warning in &loaded.warnings {
+        eprintln!("WARNING {}", warning);
+    }
+    let format = Format::from_path(&output);
+    if format == Format::Legacy && loaded.data.iter().any(|c| c.has_metadata()) {
+        eprintln!(
+            "WARNING {} format drops difficulty, notes and stance",
+            format
+        );
+    }
+    let content = write(format, &loaded)?;
+    fs::write(&output, content).map_err(|e| format!("{}: {}", output.display(), e))?;
+    Ok(loaded.data.len())
+}
+
+fn line_of(content: &str, offset: usize) -> usize {
+    content[..offset.min(content.len())].matches('\n').count() + 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    const TOML: &str = r#"
+[[combination]]
+description = "f1-2b-slip-3"
+distance = "long"
+difficulty = 3
+tags = ["counter", "southpaw"]
+notes = "Keep the slip small"
+stance = "southpaw"
+
+[[combination]]
+description = "1-2"
+distance = "short"
+body = true
+"#;
+
+    #[test]
+    fn test_from_path() {
+        assert_eq!(Format::from_path(Path::new("a/drills.TOML")), Format::Toml);
+        assert_eq!(Format::from_path(Path::new("drills.json")), Format::Json);
+        assert_eq!(Format::from_path(Path::new("drills.txt")), Format::Legacy);
+        assert_eq!(Format::from_path(Path::new("drills")), Format::Legacy);
+    }
+
+    #[test]
+    fn test_parse_toml() {
+        let (parsed, _) = parse_structured(Format::Toml, TOML, "petr");
+        let first = parsed[0].as_ref().unwrap();
+        assert_eq!(first.defense, Defense::Yes);
+        assert_eq!(first.faint, Faint::Yes);
+        assert_eq!(first.body, Body::Yes);
+        assert_eq!(first.difficulty, Some(3));
+        assert_eq!(
+            first.tags,
+            vec!["counter".to_owned(), "southpaw".to_owned()]
+        );
+        assert_eq!(first.notes.as_deref(), Some("Keep the slip small"));
+        assert_eq!(first.stance, Some(Stance::Southpaw));
+        assert_eq!(first.library, "petr");
+        let second = parsed[1].as_ref().unwrap();
+        assert_eq!(second.distance, Distance::Short);
+        assert_eq!(second.body, Body::Yes);
+        assert!(second.tags.is_empty());
+    }
+
+    #[test]
+    fn test_parse_json() {
+        let json =
+            r#"{"combination": [{"description": "1-2-3b", "distance": "long", "defense": false}]}"#;
+        let (parsed, _) = parse_structured(Format::Json, json, "petr");
+        assert_eq!(parsed.len(), 1);
+        assert_eq!(parsed[0].as_ref().unwrap().body, Body::Yes);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let (parsed, _) = parse_structured(
+            Format::Toml,
+            "[[combination]]\ndescription = \"1-2\"\ndistance = \"middle\"\n",
+            "",
+        );
+        let error = parsed[0].as_ref().unwrap_err();
+        assert_eq!(error.line, Some(3));
+        assert!(error.message.contains("middle"), "{}", error.message);
+
+        let json = r#"{"combination": [{"description": "1-9", "distance": "long"}]}"#;
+        let (parsed, _) = parse_structured(Format::Json, json, "");
+        assert!(
+            parsed[0]
+                .as_ref()
+                .unwrap_err()
+                .message
+                .starts_with("combination 1: Cannot derive defense")
+        );
+
+        let json =
+            r#"{"combination": [{"description": "1-2", "distance": "long", "difficulty": 9}]}"#;
+        let (parsed, _) = parse_structured(Format::Json, json, "");
+        assert!(
+            parsed[0]
+                .as_ref()
+                .unwrap_err()
+                .message
+                .starts_with("combination 1: Difficulty 9")
+        );
+    }
+
+    // Only legacy files keep the text a combination was read from.
+    fn without_file(data: &[Rc<Combination>]) -> Vec<Combination> {
+        data.iter()
+            .map(|c| Combination {
+                source: PathBuf::new(),
+                raw: String::new(),
+                ..c.as_ref().clone()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let dir = TempDir::new("round_trip");
+        let source = "./combinations.txt".to_owned();
+        let original = fs::read_to_string(&source).unwrap();
+        let options = LoadOptions {
+            consistency: Consistency::Warn,
+            skip_invalid: false,
+        };
+        let expected = combination::load_data(Path::new(&source), options).unwrap();
+        for name in ["combinations.toml", "combinations.json", "combinations.txt"] {
+            let output = dir.join(name);
+            let count = convert(&[source.clone(), output.to_string_lossy().into_owned()]).unwrap();
+            let converted = combination::load_data(&output, options).unwrap();
+            assert_eq!(converted.data.len(), count);
+            assert_eq!(
+                without_file(&converted.data),
+                without_file(&expected.data),
+                "{}",
+                name
+            );
+            assert_eq!(converted.comments, expected.comments, "{}", name);
+        }
+        let legacy = fs::read_to_string(dir.join("combinations.txt")).unwrap();
+        assert_eq!(legacy, original);
+
+        let input = dir.join("auto.txt");
+        fs::write(&input, "#x\n\n1-2b;long; auto; No; ;\n# end").unwrap();
+        let toml = dir.join("auto.toml").to_string_lossy().into_owned();
+        let output = dir.join("auto_out.txt").to_string_lossy().into_owned();
+        convert(&[input.to_string_lossy().into_owned(), output.clone()]).unwrap();
+        assert_eq!(
+            fs::read_to_string(&output).unwrap(),
+            "#x\n\n1-2b;long; auto; No; ;\n# end"
+        );
+        convert(&[input.to_string_lossy().into_owned(), toml.clone()]).unwrap();
+        assert!(!fs::read_to_string(&toml).unwrap().contains("body"));
+        convert(&[toml, output.clone()]).unwrap();
+        assert_eq!(
+            fs::read_to_string(&output).unwrap(),
+            "# x\n1-2b; Long; auto; No; auto;\n# end\n"
+        );
+    }
+
+    #[test]
+    fn test_write_tables() {
+        let (parsed, _) = parse_structured(Format::Toml, TOML, "petr");
+        let data: Vec<Rc<Combination>> = parsed.into_iter().map(|c| Rc::new(c.unwrap())).collect();
+        let loaded = Loaded {
+            data,
+            ..Loaded::default()
+        };
+        let csv = write(Format::Csv, &loaded).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(
+            lines[0],
+            "Description,Distance,Defense,Faint,Body,Difficulty,Tags,Stance,Notes,URL"
+        );
+        assert_eq!(
+            lines[1],
+            "f1-2b-slip-3,Long,Yes,Yes,Yes,3,\"counter, southpaw\",Southpaw,Keep the slip small,"
+        );
+        let markdown = write(Format::Markdown, &loaded).unwrap();
+        assert_eq!(
+            markdown.lines().nth(3),
+            Some("| 1-2 | Short | No | No | Yes |  |  |  |  |  |")
+        );
+        assert!(
+            convert(&["only.txt".to_owned()])
+                .unwrap_err()
+                .starts_with("Usage")
+        );
+    }
+}
@@ -0,0 +1,234 @@
+use std::{collections::HashSet, path::PathBuf};
+
+use rand::{Rng, seq::SliceRandom};
+
+use crate::combination::{Combination, Distance};
+use crate::notation::{self, Notation};
+
+// Generated combinations belong to this library until they are saved.
+pub const LIBRARY: &str = "generated";
+pub const PUNCHES: [u8; 6] = [1, 2, 3, 4, 5, 6];
+pub const LENGTHS: [usize; 7] = [2, 3, 4, 5, 6, 7, 8];
+pub const BODY_PERCENTS: [u8; 6] = [0, 10, 20, 30, 50, 100];
+pub const COUNTS: [usize; 4] = [5, 10, 20, 50];
+const MIN_LENGTH: usize = 2;
+const DEFENSES: [&str; 5] = ["slip", "roll", "lean_back", "block", "step_back"];
+// Gives up on rules that allow fewer distinct combinations than requested.
+const ATTEMPTS_PER_COMBINATION: usize = 20;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rules {
+    pub punches: Vec<u8>,
+    pub max_length: usize,
+    pub body_percent: u8,
+    pub defense: bool,
+    pub avoid_same_hand: bool,
+    pub count: usize,
+}
+
+// The generator screen: the rules and where to save the results.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Generator {
+    pub rules: Rules,
+    pub library: PathBuf,
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Rules {
+            punches: PUNCHES.to_vec(),
+            max_length: 4,
+            body_percent: 20,
+            defense: false,
+            avoid_same_hand: true,
+            count: 10,
+        }
+    }
+}
+
+impl Rules {
+    pub fn toggle_punch(&mut self, punch: u8) {
+        if self.punches.contains(&punch) {
+            self.punches.retain(|p| *p != punch);
+        } else {
+            self.punches.push(punch);
+            self.punches.sort();
+        }
+    }
+}
+
+// Odd punches come from the lead hand, even ones from the rear hand. Two in
+// a row from the same hand are awkward, except for the double jab.
+fn awkward(previous: u8, next: u8) -> bool {
+    previous % 2 == next % 2 && !(previous == 1 && next == 1)
+}
+
+fn sequence<R: Rng>(rules: &Rules, rng: &mut R) -> Option<String> {
+    let length = rng.gen_range(MIN_LENGTH.min(rules.max_length)..=rules.max_length);
+    let mut punches: Vec<u8> = vec![];
+    for _ in 0..length {
+        let choices: Vec<u8> = rules
+            .punches
+            .iter()
+            .copied()
+            .filter(|p| {
+                !rules.avoid_same_hand || punches.last().is_none_or(|last| !awkward(*last, *p))
+            })
+            .collect();
+        punches.push(*choices.choose(rng)?);
+    }
+    let body = f64::from(rules.body_percent.min(100)) / 100.0;
+    let mut tokens: Vec<String> = punches
+        .iter()
+        .map(|p| {
+            if rng.gen_bool(body) {
+                format!("{}b", p)
+            } else {
+                p.to_string()
+            }
+        })
+        .collect();
+    if rules.defense {
+        let position = rng.gen_range(1..=tokens.len());
+        tokens.insert(position, DEFENSES.choose(rng)?.to_string());
+    }
+    Some(tokens.join("-"))
+}
+
+// Mostly hooks and uppercuts are thrown from short distance.
+fn distance(notation: &Notation) -> Distance {
+    let punches: Vec<u8> = notation.punches().iter().map(|p| p.number).collect();
+    let short = punches.iter().filter(|p| **p >= 3).count();
+    if short * 2 > punches.len() {
+        Distance::Short
+    } else {
+        Distance::Long
+    }
+}
+
+fn combination(description: String) -> Option<Combination> {
+    let notation = notation::parse(&description).ok()?;
+    let distance = distance(&notation);
+    Some(Combination::from_notation(
+        description,
+        &notation,
+        distance,
+        LIBRARY,
+    ))
+}
+
+// Up to rules.count new combinations, none of them among the existing
+// descriptions.
+pub fn generate<R: Rng>(
+    rules: &Rules,
+    existing: &HashSet<String>,
+    rng: &mut R,
+) -> Vec<Combination> {
+    let mut seen = existing.clone();
+    let mut generated = vec![];
+    for _ in 0..rules.count * ATTEMPTS_PER_COMBINATION {
+        if generated.len() == rules.count {
+            break;
+        }
+        let Some(description) = sequence(rules, rng) else {
+            break;
+        };
+        if !seen.insert(description.clone()) {
+            continue;
+        }
+        if let Some(combination) = combination(description) {
+            generated.push(combination);
+        }
+    }
+    generated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::combination::{Body, Defense};
+    use rand::{SeedableRng, rngs::StdRng};
+
+    fn numbers(combination: &Combination) -> Vec<u8> {
+        let notation = combination.notation().unwrap();
+        notation.punches().iter().map(|p| p.number).collect()
+    }
+
+    #[test]
+    fn test_rules() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let rules = Rules {
+            punches: vec![1, 2, 3],
+            max_length: 3,
+            body_percent: 0,
+            count: 10,
+            ..Rules::default()
+        };
+        let generated = generate(&rules, &HashSet::new(), &mut rng);
+        assert_eq!(generated.len(), 10);
+        for combination in &generated {
+            let punches = numbers(combination);
+            assert!(
+                (2..=3).contains(&punches.len()),
+                "{}",
+                combination.description
+            );
+            assert!(punches.iter().all(|p| [1, 2, 3].contains(p)));
+            assert!(punches.windows(2).all(|pair| !awkward(pair[0], pair[1])));
+            assert_eq!(combination.body, Body::No);
+            assert_eq!(combination.library, LIBRARY);
+        }
+        let descriptions: HashSet<&String> = generated.iter().map(|c| &c.description).collect();
+        assert_eq!(descriptions.len(), 10);
+    }
+
+    #[test]
+    fn test_body_and_defense() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let rules = Rules {
+            body_percent: 100,
+            defense: true,
+            count: 5,
+            ..Rules::default()
+        };
+        for combination in generate(&rules, &HashSet::new(), &mut rng) {
+            let notation = combination.notation().unwrap();
+            assert!(notation.punches().iter().all(|p| p.body));
+            assert!(notation.has_defense(), "{}", combination.description);
+            assert_eq!(combination.defense, Defense::Yes);
+            assert!(!combination.description.starts_with(char::is_alphabetic));
+        }
+    }
+
+    #[test]
+    fn test_exhausted_rules() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let rules = Rules {
+            punches: vec![1, 2],
+            max_length: 2,
+            body_percent: 0,
+            count: 10,
+            ..Rules::default()
+        };
+        let existing = HashSet::from(["1-2".to_owned()]);
+        let mut generated: Vec<String> = generate(&rules, &existing, &mut rng)
+            .into_iter()
+            .map(|c| c.description)
+            .collect();
+        generated.sort();
+        assert_eq!(generated, vec!["1-1", "2-1"]);
+        let none = Rules {
+            punches: vec![],
+            ..Rules::default()
+        };
+        assert!(generate(&none, &existing, &mut rng).is_empty());
+    }
+
+    #[test]
+    fn test_distance() {
+        let hooks = combination("3-4-5".to_owned()).unwrap();
+        assert_eq!(hooks.distance, Distance::Short);
+        let straight = combination("1-2-3".to_owned()).unwrap();
+        assert_eq!(straight.distance, Distance::Long);
+    }
+}
@@ -0,0 +1,274 @@
+use std::{
+    fmt,
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use chrono::{Local, TimeZone};
+use serde::{Deserialize, Serialize};
+
+use crate::config;
+use crate::presets::Order;
+
+const JOURNAL_FILE: &str = "journal.jsonl";
+const DATE_FORMAT: &str = "%Y-%m-%d %H:%M";
+const TIME_FORMAT: &str = "%H:%M:%S";
+
+// One line of the session log, written whenever a combination is shown.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entry {
+    // Unix time in seconds the session started, which identifies it.
+    pub session: i64,
+    pub at: i64,
+    pub combination: String,
+    pub library: String,
+    pub order: Order,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+    pub filters: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub round: Option<usize>,
+    pub punches: usize,
+}
+
+// The last line of a session, written when the window is closed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct End {
+    pub session: i64,
+    pub ended: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+enum Line {
+    Entry(Entry),
+    End(End),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Session {
+    pub started: i64,
+    pub entries: Vec<Entry>,
+    // None if the app didn't get to write the end, e.g. after a crash.
+    pub ended: Option<i64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Journal {
+    pub sessions: Vec<Session>,
+    pub selected: Option<usize>,
+}
+
+impl Session {
+    pub fn new(started: i64) -> Self {
+        Session {
+            started,
+            entries: vec![],
+            ended: None,
+        }
+    }
+
+    // Until the session ended, or the last combination was shown if the end
+    // is missing.
+    pub fn duration(&self) -> i64 {
+        self.ended
+            .or(self.entries.last().map(|e| e.at))
+            .map(|end| end - self.started)
+            .unwrap_or_default()
+    }
+
+    pub fn end(&self) -> Option<String> {
+        self.ended
+            .map(|ended| format!("{}  Session ended", local(ended, TIME_FORMAT)))
+    }
+
+    pub fn punches(&self) -> usize {
+        self.entries.iter().map(|e| e.punches).sum()
+    }
+}
+
+impl Journal {
+    pub fn path() -> PathBuf {
+        config::config_dir().join(JOURNAL_FILE)
+    }
+
+    // Newest session first. Lines that don't parse, e.g. one cut short by a
+    // crash, are skipped with a warning.
+    pub fn load(path: &Path) -> io::Result<Journal> {
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e),
+        };
+        let mut sessions: Vec<Session> = vec![];
+        for (index, line) in content.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let entry = match serde_json::from_str(line) {
+                Ok(Line::Entry(entry)) => entry,
+                Ok(Line::End(end)) => {
+                    if let Some(session) =
+                        sessions.iter_mut().rev().find(|s| s.started == end.session)
+                    {
+                        session.ended = Some(end.ended);
+                    }
+                    continue;
+                }
+                Err(e) => {
+                    eprintln!(
+                        "WARNING Skipping line {} of {}: {}",
+                        index + 1,
+                        path.display(),
+                        e
+                    );
+                    continue;
+                }
+            };
+            match sessions.last_mut() {
+                Some(session) if session.started == entry.session => session.entries.push(entry),
+                _ => {
+                    let mut session = Session::new(entry.session);
+                    session.entries.push(entry);
+                    sessions.push(session);
+                }
+            }
+        }
+        sessions.reverse();
+        Ok(Journal {
+            sessions,
+            selected: None,
+        })
+    }
+
+    pub fn append<T: Serialize>(path: &Path, line: &T) -> io::Result<()> {
+        let line = serde_json::to_string(line)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        writeln!(file, "{}", line)
+    }
+
+    pub fn session(&self) -> Option<&Session> {
+        self.selected.and_then(|index| self.sessions.get(index))
+    }
+}
+
+fn local(time: i64, format: &str) -> String {
+    Local
+        .timestamp_opt(time, 0)
+        .single()
+        .map(|time| time.format(format).to_string())
+        .unwrap_or_default()
+}
+
+impl fmt::Display for Session {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let duration = self.duration();
+        write!(
+            f,
+            "{}  {}:{:02} min  {} combinations  ~{} punches",
+            local(self.started, DATE_FORMAT),
+            duration / 60,
+            duration % 60,
+            self.entries.len(),
+            self.punches()
+        )
+    }
+}
+
+impl fmt::Display for Entry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}  {}", local(self.at, TIME_FORMAT), self.combination)?;
+        if !self.library.is_empty() {
+            write!(f, "  [{}]", self.library)?;
+        }
+        if let Some(round) = self.round {
+            write!(f, "  round {}", round)?;
+        }
+        write!(f, "  {}", self.order)?;
+        if let Some(seed) = self.seed {
+            write!(f, " (seed {})", seed)?;
+        }
+        if !self.filters.is_empty() {
+            write!(f, "  {}", self.filters)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    fn entry(session: i64, at: i64, combination: &str, punches: usize) -> Entry {
+        Entry {
+            session,
+            at,
+            combination: combination.to_owned(),
+            library: "basics".to_owned(),
+            order: Order::Random,
+            seed: Some(42),
+            filters: "+body".to_owned(),
+            round: Some(2),
+            punches,
+        }
+    }
+
+    #[test]
+    fn test_session() {
+        let mut session = Session::new(1000);
+        assert_eq!((session.duration(), session.punches()), (0, 0));
+        session.entries.push(entry(1000, 1010, "1-2", 2));
+        session.entries.push(entry(1000, 1125, "1-2-3b", 3));
+        assert_eq!((session.duration(), session.punches()), (125, 5));
+        assert!(
+            session
+                .to_string()
+                .ends_with("2:05 min  2 combinations  ~5 punches")
+        );
+        assert_eq!(session.end(), None);
+        session.ended = Some(1200);
+        assert_eq!(session.duration(), 200);
+        assert!(session.end().unwrap().ends_with("  Session ended"));
+        let line = session.entries[1].to_string();
+        assert!(
+            line.ends_with("1-2-3b  [basics]  round 2  Random (seed 42)  +body"),
+            "{}",
+            line
+        );
+    }
+
+    #[test]
+    fn test_append_and_load() {
+        let dir = TempDir::new("journal_append_and_load");
+        let path = dir.join(JOURNAL_FILE);
+        assert!(Journal::load(&path).unwrap().sessions.is_empty());
+        Journal::append(&path, &entry(1000, 1010, "1-2", 2)).unwrap();
+        Journal::append(&path, &entry(1000, 1020, "1-1-2", 3)).unwrap();
+        let end = End {
+            session: 1000,
+            ended: 1030,
+        };
+        Journal::append(&path, &end).unwrap();
+        let mut last = entry(2000, 2010, "slip-2", 1);
+        (last.seed, last.round) = (None, None);
+        Journal::append(&path, &last).unwrap();
+        let mut content = fs::read_to_string(&path).unwrap();
+        assert!(!content.contains("\"round\":null"), "{}", content);
+        content.push_str("{\"session\":3000,\"at\"\n");
+        fs::write(&path, content).unwrap();
+
+        let journal = Journal::load(&path).unwrap();
+        let started: Vec<i64> = journal.sessions.iter().map(|s| s.started).collect();
+        assert_eq!(started, vec![2000, 1000]);
+        assert_eq!(journal.sessions[0].entries, vec![last]);
+        assert_eq!(journal.sessions[0].ended, None);
+        assert_eq!(journal.sessions[1].entries.len(), 2);
+        assert_eq!(journal.sessions[1].ended, Some(1030));
+    }
+}
@@ -0,0 +1,249 @@
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use iced::keyboard::{Key, key::Named};
+
+use crate::config;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Shortcut {
+    Named(Named),
+    Character(char),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Next,
+    Previous,
+    Clean,
+    Sloppy,
+    Failed,
+    Reset,
+    InOrder,
+    Weighted,
+    Due,
+    Reload,
+    Show,
+    Start,
+    Pause,
+    Stop,
+    Distance,
+    Defence,
+    Faint,
+    Body,
+}
+
+#[derive(Debug, Clone)]
+pub struct Bindings {
+    keys: HashMap<Shortcut, Action>,
+}
+
+const BINDINGS_FILE: &str = "bindings.txt";
+const COMMENT: &str = "#";
+const ASSIGN: &str = "=";
+const DELIMITER: &str = ",";
+
+const ACTIONS: [(&str, Action); 18] = [
+    ("next", Action::Next),
+    ("previous", Action::Previous),
+    ("clean", Action::Clean),
+    ("sloppy", Action::Sloppy),
+    ("failed", Action::Failed),
+    ("reset", Action::Reset),
+    ("in_order", Action::InOrder),
+    ("weighted", Action::Weighted),
+    ("due", Action::Due),
+    ("reload", Action::Reload),
+    ("show", Action::Show),
+    ("start", Action::Start),
+    ("pause", Action::Pause),
+    ("stop", Action::Stop),
+    ("distance", Action::Distance),
+    ("defence", Action::Defence),
+    ("faint", Action::Faint),
+    ("body", Action::Body),
+];
+
+const NAMED: [(&str, Named); 14] = [
+    ("space", Named::Space),
+    ("enter", Named::Enter),
+    ("escape", Named::Escape),
+    ("tab", Named::Tab),
+    ("backspace", Named::Backspace),
+    ("left", Named::ArrowLeft),
+    ("right", Named::ArrowRight),
+    ("up", Named::ArrowUp),
+    ("down", Named::ArrowDown),
+    ("page_up", Named::PageUp),
+    ("page_down", Named::PageDown),
+    ("home", Named::Home),
+    ("end", Named::End),
+    ("delete", Named::Delete),
+];
+
+const DEFAULTS: [(&str, &str); 18] = [
+    ("next", "space, right, page_down"),
+    ("previous", "left, backspace, page_up"),
+    ("clean", "1"),
+    ("sloppy", "2"),
+    ("failed", "3"),
+    ("reset", "r"),
+    ("in_order", "o"),
+    ("weighted", "g"),
+    ("due", "u"),
+    ("reload", "l"),
+    ("show", "w"),
+    ("start", "s, enter"),
+    ("pause", "p"),
+    ("stop", "x, escape"),
+    ("distance", "d"),
+    ("defence", "e"),
+    ("faint", "f"),
+    ("body", "b"),
+];
+
+impl Shortcut {
+    pub fn from_key(key: &Key) -> Option<Shortcut> {
+        match key {
+            Key::Named(named) => Some(Shortcut::Named(*named)),
+            Key::Character(c) => {
+                let mut chars = c.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => Some(Shortcut::Character(c.to_ascii_lowercase())),
+                    _ => None,
+                }
+            }
+            Key::Unidentified => None,
+        }
+    }
+
+    fn parse(name: &str) -> Option<Shortcut> {
+        let name = name.trim();
+        if let Some((_, named)) = NAMED.iter().find(|(n, _)| n.eq_ignore_ascii_case(name)) {
+            return Some(Shortcut::Named(*named));
+        }
+        let mut chars = name.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Some(Shortcut::Character(c.to_ascii_lowercase())),
+            _ => None,
+        }
+    }
+}
+
+impl Bindings {
+    pub fn get(&self, shortcut: Shortcut) -> Option<Action> {
+        self.keys.get(&shortcut).copied()
+    }
+
+    pub fn path() -> PathBuf {
+        config::config_dir().join(BINDINGS_FILE)
+    }
+
+    // A missing file keeps the default bindings.
+    pub fn load(path: &Path) -> io::Result<Bindings> {
+        match fs::read_to_string(path) {
+            Ok(content) => Ok(Bindings::parse(&content)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Bindings::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    // Lines have the form "next = space, right". An action listed in the
+    // file loses all of its default keys.
+    pub fn parse(content: &str) -> Bindings {
+        let mut bindings = Bindings::default();
+        for line in content.lines() {
+            let line = line.trim();
+            if line.starts_with(COMMENT) || line.is_empty() {
+                continue;
+            }
+            if let Err(e) = bindings.bind_line(line) {
+                eprintln!("WARNING {} in {:?}", e, line);
+            }
+        }
+        bindings
+    }
+
+    fn bind_line(&mut self, line: &str) -> Result<(), String> {
+        let (name, keys) = line
+            .split_once(ASSIGN)
+            .ok_or(format!("Expect action {} keys", ASSIGN))?;
+        let name = name.trim();
+        let action = ACTIONS
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, action)| *action)
+            .ok_or(format!("Unknown action {:?}", name))?;
+        let mut shortcuts = vec![];
+        for key in keys.split(DELIMITER) {
+            shortcuts.push(Shortcut::parse(key).ok_or(format!("Unknown key {:?}", key.trim()))?);
+        }
+        self.keys.retain(|_, a| *a != action);
+        for shortcut in shortcuts {
+            self.keys.insert(shortcut, action);
+        }
+        Ok(())
+    }
+}
+
+impl Default for Bindings {
+    fn default() -> Self {
+        let mut bindings = Bindings {
+            keys: HashMap::new(),
+        };
+        for (action, keys) in DEFAULTS {
+            bindings
+                .bind_line(&format!("{} {} {}", action, ASSIGN, keys))
+                .expect("Invalid default bindings");
+        }
+        bindings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_bindings() {
+        let bindings = Bindings::default();
+        assert_eq!(
+            bindings.get(Shortcut::Named(Named::Space)),
+            Some(Action::Next)
+        );
+        assert_eq!(
+            bindings.get(Shortcut::Named(Named::ArrowLeft)),
+            Some(Action::Previous)
+        );
+        assert_eq!(bindings.get(Shortcut::Character('r')), Some(Action::Reset));
+        assert_eq!(bindings.get(Shortcut::Character('q')), None);
+    }
+
+    #[test]
+    fn test_parse_bindings() {
+        let bindings = Bindings::parse("# pedal\nnext = n, Enter\nunknown = q\nreset = ??\n");
+        assert_eq!(bindings.get(Shortcut::Character('n')), Some(Action::Next));
+        assert_eq!(
+            bindings.get(Shortcut::Named(Named::Enter)),
+            Some(Action::Next)
+        );
+        assert_eq!(bindings.get(Shortcut::Named(Named::Space)), None);
+        assert_eq!(bindings.get(Shortcut::Character('r')), Some(Action::Reset));
+    }
+
+    #[test]
+    fn test_shortcut_from_key() {
+        assert_eq!(
+            Shortcut::from_key(&Key::Character("R".into())),
+            Some(Shortcut::Character('r'))
+        );
+        assert_eq!(
+            Shortcut::from_key(&Key::Named(Named::Space)),
+            Some(Shortcut::Named(Named::Space))
+        );
+        assert_eq!(Shortcut::from_key(&Key::Unidentified), None);
+    }
+}
@@ -1,10 +1,51 @@
+mod audio;
 mod combination;
+mod config;
+mod editor;
+mod format;
+mod generator;
+mod journal;
+mod keys;
 mod model;
+mod notation;
+mod practice;
+mod presets;
+mod query;
+mod schedule;
+mod search;
+mod state;
+mod stats;
+mod tags;
+#[cfg(test)]
+mod testing;
+mod timer;
 mod view;
-use crate::view::{update, view};
+use std::{env, process};
+
+use crate::state::State;
+use crate::view::{boot, subscription, update, view};
+
+const CONVERT: &str = "convert";
 
 fn main() -> iced::Result {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some(CONVERT) {
+        match format::convert(&args[1..]) {
+            Ok(count) => println!("Wrote {} combinations to {}", count, args[args.len() - 1]),
+            Err(e) => {
+                eprintln!("ERROR {}", e);
+                process::exit(1);
+            }
+        }
+        return Ok(());
+    }
+    let state = State::load(&State::path()).unwrap_or_else(|e| {
+        eprintln!("ERROR {}", e);
+        State::default()
+    });
     iced::application("Boxing Trainer", update, view)
-        .window_size((2000.0, 800.0))
-        .run()
+        .subscription(subscription)
+        .window_size((state.width, state.height))
+        .exit_on_close_request(false)
+        .run_with(move || boot(state))
 }
@@ -1,55 +1,191 @@
-use chrono::prelude::*;
 use rand::{SeedableRng, prelude::SliceRandom, rngs::StdRng};
-use std::{process, rc::Rc, env, process::Command};
+use std::{collections::{BTreeSet, HashSet}, ops::Range, rc::Rc, env, path::{Path, PathBuf}, process::Command, time::{Duration, Instant}};
 
+use chrono::{Local, Utc};
+use iced::Size;
 use iced::widget::scrollable::Id;
 
-use crate::combination::{self, Body, Combination, Defense, Distance, Faint};
+use crate::audio::{AudioBackend, CommandLine, Cue, ProcessBackend, Sounds};
+use crate::config::Config;
+use crate::combination::{self, Combination, Consistency, LoadOptions, Loaded};
+use crate::editor::{self, Editor};
+use crate::journal::{self, Journal, Session};
+use crate::generator::{self, Generator, Rules};
+use crate::keys::{Action, Bindings, Shortcut};
+use crate::practice::{self, Practice, Stats};
+use crate::presets::{Order, Preset, Presets};
+use crate::query::{self, Query};
+use crate::schedule::{self, Rating};
+use crate::search::{self, Mode};
+use crate::stats::{self, Dashboard};
+use crate::state::{self, State};
+use crate::tags::{TagFilter, TagState};
+use crate::timer::{Pace, Phase, RoundTimer, Seconds, TimerEvent};
 
-const PATH: &str = "./combinations.txt";
+const STRICT_KEY: &str = "BOXING_TRAINER_STRICT";
+const SKIP_INVALID_KEY: &str = "BOXING_TRAINER_SKIP_INVALID";
+const TTS_KEY: &str = "BOXING_TRAINER_TTS";
+const PLAYER_KEY: &str = "BOXING_TRAINER_PLAYER";
+const SOUNDS_KEY: &str = "BOXING_TRAINER_SOUNDS";
+const SOUNDS_DIR: &str = "./sounds";
+#[cfg(target_os = "macos")]
+const DEFAULT_PLAYER: &str = "afplay";
+#[cfg(not(target_os = "macos"))]
+const DEFAULT_PLAYER: &str = "aplay -q";
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum DistanceSelection {
-    Short,
-    Long,
-    All,
-}
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum DefenceSelection {
-    Yes,
-    No,
-    All,
-}
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum FaintSelection {
-    Yes,
-    No,
-    All,
-}
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum BodySelection {
-    Yes,
-    No,
-    All,
+// The files the model reads and writes, all in the config directory
+// unless a test puts them elsewhere.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Paths {
+    pub state: PathBuf,
+    pub practice: PathBuf,
+    pub presets: PathBuf,
+    pub journal: PathBuf,
+    pub bindings: PathBuf,
 }
 
 #[derive(Debug, Clone)]
 pub struct Model {
     number: usize,
     current: usize,
-    distance_selection: Option<DistanceSelection>,
-    defence_selection: Option<DefenceSelection>,
-    faint_selection: Option<FaintSelection>,
-    body_selection: Option<BodySelection>,
+    tag_filter: TagFilter,
+    query_text: String,
+    query: Query,
+    query_error: Option<String>,
+    search_text: String,
+    search_mode: Mode,
+    order: Order,
+    seed: u64,
+    seed_text: String,
+    rng: StdRng,
+    history: Vec<usize>,
+    practice: Practice,
+    session: Session,
+    journal: Option<Journal>,
+    dashboard: Option<Dashboard>,
+    generated: Vec<Rc<Combination>>,
+    generator: Option<Generator>,
+    window_size: Size,
+    presets: Presets,
+    preset: Option<String>,
+    preset_name: String,
     combinations: Vec<Rc<Combination>>,
+    // The loaded and generated combinations in order, shuffled only on
+    // Reset, Reload or a new seed so that filtering keeps their order.
+    arranged: Vec<Rc<Combination>>,
     data: Vec<Rc<Combination>>,
+    libraries: Vec<PathBuf>,
+    errors: Vec<String>,
     scrollable_id: iced::widget::scrollable::Id,
+    timer: RoundTimer,
+    last_tick: Option<Instant>,
+    pace: Pace,
+    until_callout: Duration,
+    audio: Rc<dyn AudioBackend>,
+    speech: bool,
+    sounds: Sounds,
+    sound: bool,
+    bindings: Bindings,
+    editor: Option<Editor>,
+    paths: Paths,
+}
+
+impl Default for Paths {
+    fn default() -> Self {
+        Paths {
+            state: State::path(),
+            practice: Practice::path(),
+            presets: Presets::path(),
+            journal: Journal::path(),
+            bindings: Bindings::path(),
+        }
+    }
 }
 
 impl Model {
+    pub fn new(state: State) -> Self {
+        Model::with(Config::load(), state, Paths::default(), audio_backend())
+    }
+
+    pub fn with(config: Config, state: State, paths: Paths, audio: Rc<dyn AudioBackend>) -> Self {
+        let Config { libraries, seed } = config;
+        let (data, errors) = match combination::load_libraries(&libraries, load_options()) {
+            Ok(loaded) => {
+                let errors = messages(&loaded);
+                (loaded.data, errors)
+            }
+            Err(e) => {
+                eprintln!("ERROR {}", e);
+                (vec![], e.messages())
+            }
+        };
+        let mut s = Self {
+            number: 1,
+            current: 0,
+            tag_filter: state.tags,
+            query_text: String::new(),
+            query: Query::all(),
+            query_error: None,
+            search_text: state.search,
+            search_mode: Mode::Substring,
+            order: state.order,
+            seed: state.seed,
+            seed_text: state.seed.to_string(),
+            rng: StdRng::seed_from_u64(state.seed),
+            history: vec![],
+            session: Session::new(Utc::now().timestamp()),
+            journal: None,
+            dashboard: None,
+            generated: vec![],
+            generator: None,
+            practice: Practice::load(&paths.practice).unwrap_or_else(|e| {
+                eprintln!("ERROR {}", e);
+                Practice::default()
+            }),
+            window_size: Size::new(state.width, state.height),
+            presets: Presets::load(&paths.presets).unwrap_or_else(|e| {
+                eprintln!("ERROR {}", e);
+                Presets::default()
+            }),
+            preset: None,
+            preset_name: String::new(),
+            combinations: data.clone(),
+            arranged: data.clone(),
+            data,
+            libraries,
+            errors,
+            scrollable_id: Id::unique(),
+            timer: RoundTimer::default(),
+            last_tick: None,
+            pace: Pace::Off,
+            until_callout: Duration::ZERO,
+            audio,
+            speech: env::var(TTS_KEY).is_ok(),
+            sounds: Sounds::from_dir(Path::new(
+                &env::var(SOUNDS_KEY).unwrap_or(SOUNDS_DIR.to_owned()),
+            )),
+            sound: true,
+            bindings: Bindings::load(&paths.bindings).unwrap_or_else(|e| {
+                eprintln!("ERROR {}", e);
+                Bindings::default()
+            }),
+            editor: None,
+            paths,
+        };
+        s.parse_query(state.query);
+        s.arrange();
+        // The library may have shrunk since the state was saved.
+        if state.current < s.combinations.len() {
+            s.current = state.current;
+            s.number = state.number;
+        }
+        // A seed from the command line starts that sequence from the top.
+        if let Some(seed) = seed {
+            s.set_seed(seed);
+        }
+        s
+    }
+
     pub fn current(&self) -> usize {
         self.current
     }
@@ -69,61 +205,257 @@ impl Model {
         self.combinations[self.current].description.clone()
     }
 
+    pub fn current_combination(&self) -> Option<&Rc<Combination>> {
+        self.combinations.get(self.current)
+    }
+
     pub fn combinations(&self) -> &Vec<Rc<Combination>> {
         &self.combinations
     }
 
-    pub fn distance_selection(&self) -> Option<DistanceSelection> {
-        self.distance_selection
+    pub fn has_libraries(&self) -> bool {
+        self.libraries.len() > 1
     }
 
-    pub fn set_distance_selection(&mut self, option: DistanceSelection) {
-        self.distance_selection = Some(option);
-        self.update_filter();
+    pub fn library(&self) -> String {
+        match self.combinations.get(self.current) {
+            Some(combination) => combination.library.clone(),
+            None => "".to_owned(),
+        }
     }
 
-    pub fn defence_selection(&self) -> Option<DefenceSelection> {
-        self.defence_selection
+    // Every tag of the loaded combinations, sorted.
+    pub fn tags(&self) -> Vec<String> {
+        let tags: BTreeSet<String> = self
+            .data
+            .iter()
+            .chain(&self.generated)
+            .flat_map(|c| c.all_tags())
+            .collect();
+        tags.into_iter().collect()
     }
 
-    pub fn set_defence_selection(&mut self, option: DefenceSelection) {
-        self.defence_selection = Some(option);
+    pub fn tag_state(&self, tag: &str) -> TagState {
+        self.tag_filter.get(tag)
+    }
+
+    pub fn set_tag_state(&mut self, tag: &str, state: TagState) {
+        self.tag_filter.set(tag, state);
         self.update_filter();
     }
 
-    pub fn faint_selection(&self) -> Option<FaintSelection> {
-        self.faint_selection
+    pub fn has_tag_filter(&self) -> bool {
+        !self.tag_filter.is_empty()
     }
 
-    pub fn set_faint_selection(&mut self, option: FaintSelection) {
-        self.faint_selection = Some(option);
+    pub fn clear_tag_filter(&mut self) {
+        self.tag_filter.clear();
         self.update_filter();
     }
 
-    pub fn body_selection(&self) -> Option<BodySelection> {
-        self.body_selection
+    pub fn query_text(&self) -> &str {
+        &self.query_text
+    }
+
+    pub fn query_error(&self) -> Option<&String> {
+        self.query_error.as_ref()
+    }
+
+    pub fn set_query(&mut self, text: String) {
+        if self.parse_query(text) {
+            self.update_filter();
+        }
+    }
+
+    // An invalid query keeps the previous filter until it is fixed.
+    fn parse_query(&mut self, text: String) -> bool {
+        let parsed = match query::parse(&text) {
+            Ok(query) => {
+                self.query = query;
+                self.query_error = None;
+                true
+            }
+            Err(e) => {
+                self.query_error = Some(e.to_string());
+                false
+            }
+        };
+        self.query_text = text;
+        parsed
+    }
+
+    pub fn search_text(&self) -> &str {
+        &self.search_text
     }
 
-    pub fn set_body_selection(&mut self, option: BodySelection) {
-        self.body_selection = Some(option);
+    pub fn set_search(&mut self, text: String) {
+        self.search_text = text;
         self.update_filter();
     }
 
+    // Byte ranges of the description matched by the search.
+    pub fn highlights(&self, combination: &Combination) -> Vec<Range<usize>> {
+        search::find(&self.search_text, &combination.description, self.search_mode)
+            .unwrap_or_default()
+    }
+
+    pub fn timer(&self) -> &RoundTimer {
+        &self.timer
+    }
+
+    pub fn set_round_length(&mut self, option: Seconds) {
+        self.timer.set_round_length(option);
+    }
+
+    pub fn set_rest_length(&mut self, option: Seconds) {
+        self.timer.set_rest_length(option);
+    }
+
+    pub fn set_rounds(&mut self, option: usize) {
+        self.timer.set_rounds(option);
+    }
+
+    pub fn pace(&self) -> Pace {
+        self.pace
+    }
+
+    pub fn set_pace(&mut self, option: Pace) {
+        self.pace = option;
+        self.schedule_callout();
+    }
+
+    pub fn start_timer(&mut self) -> Vec<TimerEvent> {
+        self.last_tick = None;
+        let events = self.timer.start();
+        if starts_round(&events) {
+            self.callout();
+        }
+        events
+    }
+
+    pub fn pause_timer(&mut self) {
+        self.timer.pause();
+    }
+
+    pub fn stop_timer(&mut self) {
+        self.timer.stop();
+    }
+
+    pub fn tick(&mut self, now: Instant) -> Vec<TimerEvent> {
+        let elapsed = match self.last_tick {
+            Some(last) => now.saturating_duration_since(last),
+            None => Default::default(),
+        };
+        self.last_tick = Some(now);
+        let events = self.timer.tick(elapsed);
+        if starts_round(&events) {
+            self.callout();
+        } else if self.timer.phase() == Phase::Work {
+            self.until_callout = self.until_callout.saturating_sub(elapsed);
+            if self.until_callout.is_zero() {
+                self.callout();
+            }
+        }
+        events
+    }
+
+    fn callout(&mut self) {
+        if self.pace == Pace::Off {
+            return;
+        }
+        self.next();
+        self.moved();
+        self.schedule_callout();
+    }
+
+    fn schedule_callout(&mut self) {
+        let punches = match self.combinations.get(self.current) {
+            Some(combination) => combination
+                .notation()
+                .map(|n| n.punch_count())
+                .unwrap_or_default(),
+            None => 0,
+        };
+        self.until_callout = self
+            .pace
+            .interval(punches, &mut rand::thread_rng())
+            .unwrap_or_default();
+    }
+
     pub fn reset(&mut self) {
         self.number = 1;
         self.current = 0;
+        self.history.clear();
+        if self.order == Order::Weighted {
+            self.draw();
+        }
     }
 
     pub fn reset_in_random_order(&mut self) {
-        self.reset();
+        self.set_seed(state::new_seed());
+    }
+
+    pub fn set_seed(&mut self, seed: u64) {
+        self.order = Order::Random;
+        self.seed = seed;
+        self.seed_text = seed.to_string();
+        self.rng = StdRng::seed_from_u64(seed);
+        self.arrange();
+    }
 
-        let seed = Utc::now().timestamp_millis() as u64;
-        let mut rng = StdRng::seed_from_u64(seed);
-        self.combinations.shuffle(&mut rng);
+    pub fn seed_text(&self) -> &str {
+        &self.seed_text
+    }
+
+    pub fn set_seed_text(&mut self, text: String) {
+        self.seed_text = text;
+    }
+
+    pub fn apply_seed_text(&mut self) {
+        match self.seed_text.trim().parse() {
+            Ok(seed) => self.set_seed(seed),
+            Err(_) => self
+                .errors
+                .push(format!("Seed must be a whole number, got {:?}", self.seed_text)),
+        }
     }
 
     pub fn reset_in_order(&mut self) {
-        self.reset();
+        self.order = Order::InOrder;
+        self.arrange();
+    }
+
+    pub fn reset_due(&mut self) {
+        self.order = Order::Due;
+        self.arrange();
+    }
+
+    pub fn reset_weighted(&mut self) {
+        self.order = Order::Weighted;
+        self.rng = StdRng::seed_from_u64(self.seed);
+        self.arrange();
+    }
+
+    // Draws the next combination by weight, avoiding the one just shown.
+    fn draw(&mut self) {
+        let now = Utc::now().timestamp();
+        let weights: Vec<f64> = self
+            .combinations
+            .iter()
+            .map(|c| self.practice.weight(c, now))
+            .collect();
+        let exclude = self.history.last().copied();
+        if let Some(index) = practice::draw(&weights, exclude, &mut self.rng) {
+            self.current = index;
+        }
+    }
+
+    fn arrange(&mut self) {
+        self.arranged = self.data.iter().chain(&self.generated).cloned().collect();
+        if self.order == Order::Random {
+            let mut rng = StdRng::seed_from_u64(self.seed);
+            self.arranged.shuffle(&mut rng);
+        }
         self.update_filter();
     }
 
@@ -132,13 +464,26 @@ impl Model {
             return;
         }
         self.number += 1;
-        self.current = (self.current + 1) % self.combinations.len();
+        if self.order == Order::Weighted {
+            self.history.push(self.current);
+            self.draw();
+        } else {
+            self.current = (self.current + 1) % self.combinations.len();
+        }
     }
 
     pub fn previous(&mut self) {
         if self.combinations.is_empty() {
             return;
         }
+        if self.order == Order::Weighted {
+            // Walks back through what was drawn.
+            if let Some(index) = self.history.pop() {
+                self.number += 1;
+                self.current = index;
+            }
+            return;
+        }
         self.number += 1;
 
         self.current = if self.current == 0 {
@@ -154,40 +499,516 @@ impl Model {
     }
 
     fn update_filter(&mut self) {
-        self.combinations = filter(
-            &self.data,
-            self.distance_selection.unwrap(),
-            self.defence_selection.unwrap(),
-            self.faint_selection.unwrap(),
-            self.body_selection.unwrap(),
-        );
+        let query = self.tag_filter.query().and(self.query.clone());
+        let filtered: Vec<Rc<Combination>> = self
+            .arranged
+            .iter()
+            .filter(|c| query.matches(c))
+            .cloned()
+            .collect();
+        (self.search_mode, self.combinations) = search::search(&self.search_text, &filtered);
+        if self.order == Order::Due {
+            self.due_queue();
+        }
         self.reset()
     }
 
+    // Most overdue first, then the ones never rated in library order.
+    fn due_queue(&mut self) {
+        let until = schedule::end_of_day(Local::now());
+        let practice = &self.practice;
+        self.combinations
+            .retain(|c| practice.get(c).is_none_or(|e| e.schedule.is_due(until)));
+        self.combinations.sort_by_cached_key(|c| {
+            practice
+                .get(c)
+                .and_then(|e| e.schedule.due)
+                .unwrap_or(i64::MAX)
+        });
+    }
+
+    // Rating moves on to the next combination and drops it from the due
+    // queue once it is scheduled for another day.
+    pub fn rate(&mut self, rating: Rating) {
+        let Some(combination) = self.combinations.get(self.current).cloned() else {
+            return;
+        };
+        self.practice.review(&combination, rating, Utc::now().timestamp());
+        self.save_practice();
+        let until = schedule::end_of_day(Local::now());
+        let due = self
+            .practice
+            .get(&combination)
+            .is_none_or(|e| e.schedule.is_due(until));
+        if self.order == Order::Due && !due {
+            self.combinations.remove(self.current);
+            if self.current >= self.combinations.len() {
+                self.current = 0;
+            }
+            self.number += 1;
+        } else {
+            self.next();
+        }
+    }
+
+    pub fn errors(&self) -> &Vec<String> {
+        &self.errors
+    }
+
+    pub fn dismiss_errors(&mut self) {
+        self.errors.clear();
+    }
+
+    pub fn libraries(&self) -> &Vec<PathBuf> {
+        &self.libraries
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty() && self.generated.is_empty()
+    }
+
+    // A failed reload keeps the previously loaded data.
     pub fn reload(&mut self) {
-        let data = combination::load_data(PATH);
-        if data.is_err() {
-            eprintln!("ERROR {}", data.unwrap_err());
-            process::exit(1);
+        match combination::load_libraries(&self.libraries, load_options()) {
+            Ok(loaded) => {
+                self.errors = messages(&loaded);
+                self.data = loaded.data;
+                self.arrange();
+            }
+            Err(e) => {
+                eprintln!("ERROR {}", e);
+                self.errors = e.messages();
+            }
         }
-        self.data = data.unwrap();
-        self.reset_in_random_order();
     }
 
-    pub fn show(&self) {
-        let url_op = self.combinations[self.current].url.clone();
+    pub fn speech(&self) -> bool {
+        self.speech
+    }
+
+    pub fn set_speech(&mut self, option: bool) {
+        self.speech = option;
+    }
+
+    fn announce(&self) {
+        if !self.speech {
+            return;
+        }
+        if let Some(combination) = self.combinations.get(self.current) {
+            let text = match combination.notation() {
+                Ok(notation) => notation.callout(),
+                Err(_) => combination.description.replace('_', " "),
+            };
+            self.audio.speak(&text);
+        }
+    }
+
+    pub fn sound(&self) -> bool {
+        self.sound
+    }
+
+    pub fn set_sound(&mut self, option: bool) {
+        self.sound = option;
+    }
+
+    pub fn play_cues(&self, events: &[TimerEvent]) {
+        if !self.sound {
+            return;
+        }
+        for cue in events.iter().filter_map(Cue::from_event) {
+            if let Some(path) = self.sounds.get(cue) {
+                self.audio.play(path);
+            }
+        }
+    }
+
+    pub fn action(&self, shortcut: Shortcut) -> Option<Action> {
+        self.bindings.get(shortcut)
+    }
+
+    // A minimized window reports 0×0, keep the last usable size.
+    pub fn set_window_size(&mut self, size: Size) {
+        if size.width >= state::MIN_WIDTH && size.height >= state::MIN_HEIGHT {
+            self.window_size = size;
+        }
+    }
+
+    pub fn state(&self) -> State {
+        State {
+            tags: self.tag_filter.clone(),
+            query: self.query_text.clone(),
+            search: self.search_text.clone(),
+            order: self.order,
+            seed: self.seed,
+            current: self.current,
+            number: self.number,
+            width: self.window_size.width,
+            height: self.window_size.height,
+        }
+    }
+
+    pub fn save_state(&self) {
+        let path = &self.paths.state;
+        if let Err(e) = self.state().save(path) {
+            eprintln!("ERROR Failed to save {}: {}", path.display(), e);
+        }
+        let path = &self.paths.practice;
+        if let Err(e) = self.practice.save(path) {
+            eprintln!("ERROR Failed to save {}: {}", path.display(), e);
+        }
+    }
+
+    // Logs the combination just shown to the journal.
+    fn log_combination(&mut self) {
+        let Some(combination) = self.combinations.get(self.current) else {
+            return;
+        };
+        let round = match self.timer.phase() {
+            Phase::Idle | Phase::Finished => None,
+            Phase::Work | Phase::Rest => Some(self.timer.round()),
+        };
+        let entry = journal::Entry {
+            session: self.session.started,
+            at: Utc::now().timestamp(),
+            combination: combination.description.clone(),
+            library: combination.library.clone(),
+            order: self.order,
+            seed: (self.order == Order::Random).then_some(self.seed),
+            filters: self.filters(),
+            round,
+            punches: combination.notation().map(|n| n.punch_count()).unwrap_or_default(),
+        };
+        let path = &self.paths.journal;
+        if let Err(e) = Journal::append(path, &entry) {
+            self.errors.push(format!("Failed to write {}: {}", path.display(), e));
+        }
+        self.session.entries.push(entry);
+    }
+
+    fn filters(&self) -> String {
+        let mut filters = vec![self.tag_filter.to_string(), self.query_text.trim().to_owned()];
+        if !self.search_text.trim().is_empty() {
+            filters.push(format!("search {:?}", self.search_text.trim()));
+        }
+        filters.retain(|f| !f.is_empty());
+        filters.join(", ")
+    }
+
+    // Records the end so the journal knows how long the session was.
+    pub fn end_session(&self) {
+        if self.session.entries.is_empty() {
+            return;
+        }
+        let end = journal::End {
+            session: self.session.started,
+            ended: Utc::now().timestamp(),
+        };
+        let path = &self.paths.journal;
+        if let Err(e) = Journal::append(path, &end) {
+            eprintln!("ERROR Failed to write {}: {}", path.display(), e);
+        }
+    }
+
+    pub fn journal(&self) -> Option<&Journal> {
+        self.journal.as_ref()
+    }
+
+    pub fn open_journal(&mut self) {
+        match Journal::load(&self.paths.journal) {
+            Ok(journal) => self.journal = Some(journal),
+            Err(e) => self.errors.push(e.to_string()),
+        }
+    }
+
+    pub fn select_session(&mut self, index: usize) {
+        if let Some(journal) = self.journal.as_mut() {
+            journal.selected = Some(index);
+        }
+    }
+
+    pub fn close_journal(&mut self) {
+        self.journal = None;
+    }
+
+    pub fn dashboard(&self) -> Option<&Dashboard> {
+        self.dashboard.as_ref()
+    }
+
+    // Covers everything loaded, not just the filtered combinations.
+    pub fn open_dashboard(&mut self) {
+        self.dashboard = Some(stats::dashboard(&self.data));
+    }
+
+    pub fn close_dashboard(&mut self) {
+        self.dashboard = None;
+    }
+
+    pub fn stats(&self, combination: &Combination) -> Stats {
+        self.practice.stats(combination)
+    }
+
+    pub fn priority(&self) -> Option<u8> {
+        self.combinations
+            .get(self.current)
+            .map(|c| self.practice.get(c).map_or(practice::DEFAULT_PRIORITY, |e| e.priority))
+    }
+
+    pub fn set_priority(&mut self, priority: u8) {
+        let Some(combination) = self.combinations.get(self.current) else {
+            return;
+        };
+        self.practice.set_priority(combination, priority);
+        self.save_practice();
+    }
+
+    fn save_practice(&mut self) {
+        let path = &self.paths.practice;
+        if let Err(e) = self.practice.save(path) {
+            self.errors.push(format!("Failed to save {}: {}", path.display(), e));
+        }
+    }
+
+    // After moving to another combination, e.g. with Next or a callout, but
+    // not when a filter change puts one on screen.
+    pub fn moved(&mut self) {
+        self.practiced();
+        self.log_combination();
+        self.announce();
+    }
+
+    // The current combination counts as practiced once it is shown.
+    fn practiced(&mut self) {
+        if let Some(combination) = self.combinations.get(self.current) {
+            self.practice.practiced(combination, Utc::now().timestamp());
+        }
+    }
+
+    pub fn presets(&self) -> Vec<String> {
+        self.presets.names()
+    }
+
+    pub fn preset(&self) -> Option<&String> {
+        self.preset.as_ref()
+    }
+
+    pub fn preset_name(&self) -> &str {
+        &self.preset_name
+    }
+
+    pub fn set_preset_name(&mut self, name: String) {
+        self.preset_name = name;
+    }
+
+    pub fn apply_preset(&mut self, name: String) {
+        let Some(preset) = self.presets.get(&name).cloned() else {
+            return;
+        };
+        self.tag_filter = preset.tags;
+        self.parse_query(preset.query);
+        self.search_text = preset.search;
+        self.order = preset.order;
+        self.arrange();
+        self.preset_name = name.clone();
+        self.preset = Some(name);
+    }
+
+    pub fn save_preset(&mut self) {
+        let name = self.preset_name.trim().to_owned();
+        if name.is_empty() {
+            self.errors.push("Enter a name for the preset".to_owned());
+            return;
+        }
+        self.presets.set(Preset {
+            name: name.clone(),
+            tags: self.tag_filter.clone(),
+            query: self.query_text.clone(),
+            search: self.search_text.clone(),
+            order: self.order,
+        });
+        self.preset = Some(name);
+        self.save_presets();
+    }
+
+    pub fn delete_preset(&mut self) {
+        if let Some(name) = self.preset.take() {
+            self.presets.remove(&name);
+            self.save_presets();
+        }
+    }
+
+    fn save_presets(&mut self) {
+        let path = &self.paths.presets;
+        if let Err(e) = self.presets.save(path) {
+            self.errors.push(format!("Failed to save {}: {}", path.display(), e));
+        }
+    }
+
+    pub fn editor(&self) -> Option<&Editor> {
+        self.editor.as_ref()
+    }
+
+    pub fn editor_mut(&mut self) -> Option<&mut Editor> {
+        self.editor.as_mut()
+    }
+
+    pub fn new_combination(&mut self) {
+        let library = match self.combinations.get(self.current) {
+            Some(combination) => combination.source.clone(),
+            None => self.libraries.first().cloned().unwrap_or_default(),
+        };
+        self.editor = Some(Editor::new(library));
+    }
+
+    pub fn edit_combination(&mut self) {
+        if let Some(combination) = self.combinations.get(self.current).cloned() {
+            self.editor = Some(if self.is_generated(&combination) {
+                Editor::add(combination.source.clone(), &combination)
+            } else {
+                Editor::edit(combination.source.clone(), combination)
+            });
+        }
+    }
+
+    pub fn duplicate_combination(&mut self) {
+        if let Some(combination) = self.combinations.get(self.current).cloned() {
+            self.editor = Some(if self.is_generated(&combination) {
+                Editor::add(combination.source.clone(), &combination)
+            } else {
+                Editor::duplicate(combination.source.clone(), combination)
+            });
+        }
+    }
+
+    fn is_generated(&self, combination: &Rc<Combination>) -> bool {
+        self.generated.iter().any(|c| Rc::ptr_eq(c, combination))
+    }
+
+    pub fn close_editor(&mut self) {
+        self.editor = None;
+    }
+
+    // Saving reloads all libraries and selects the saved combination.
+    pub fn save_combination(&mut self) {
+        let Some(editor) = &self.editor else {
+            return;
+        };
+        match editor.save() {
+            Ok(saved) => {
+                self.editor = None;
+                self.generated.retain(|c| c.description != saved.description);
+                self.reload();
+                if let Some(index) = self.combinations.iter().position(|c| **c == *saved) {
+                    self.set(index);
+                }
+            }
+            Err(e) => self.errors.push(e),
+        }
+    }
+
+    pub fn delete_combination(&mut self) {
+        let Some(editor) = &self.editor else {
+            return;
+        };
+        match editor.delete() {
+            Ok(()) => {
+                self.editor = None;
+                self.reload();
+            }
+            Err(e) => self.errors.push(e),
+        }
+    }
+
+    pub fn generator(&self) -> Option<&Generator> {
+        self.generator.as_ref()
+    }
+
+    pub fn generator_mut(&mut self) -> Option<&mut Generator> {
+        self.generator.as_mut()
+    }
+
+    // Saves to the library of the last generated combinations, if any.
+    pub fn open_generator(&mut self) {
+        let library = match self.generated.last() {
+            Some(combination) => combination.source.clone(),
+            None => self.libraries.first().cloned().unwrap_or_default(),
+        };
+        self.generator = Some(Generator {
+            rules: Rules::default(),
+            library,
+        });
+    }
+
+    pub fn close_generator(&mut self) {
+        self.generator = None;
+    }
+
+    pub fn generated(&self) -> &Vec<Rc<Combination>> {
+        &self.generated
+    }
+
+    // New combinations join the list and its filters until saved or
+    // discarded, duplicates of loaded ones are skipped. Edit saves them to the
+    // library picked when they were generated.
+    pub fn generate(&mut self) {
+        let Some(generator) = &self.generator else {
+            return;
+        };
+        let existing: HashSet<String> = self
+            .data
+            .iter()
+            .chain(&self.generated)
+            .map(|c| c.description.clone())
+            .collect();
+        let generated = generator::generate(&generator.rules, &existing, &mut self.rng);
+        if generated.is_empty() {
+            self.errors.push("No new combinations match the rules".to_owned());
+        }
+        let library = generator.library.clone();
+        self.generated.extend(generated.into_iter().map(|combination| {
+            Rc::new(Combination {
+                source: library.clone(),
+                ..combination
+            })
+        }));
+        self.arrange();
+    }
+
+    pub fn save_generated(&mut self) {
+        let Some(generator) = &self.generator else {
+            return;
+        };
+        match editor::append(&generator.library, &self.generated) {
+            Ok(()) => {
+                self.generated.clear();
+                self.reload();
+            }
+            Err(e) => self.errors.push(e),
+        }
+    }
+
+    pub fn discard_generated(&mut self) {
+        self.generated.clear();
+        self.arrange();
+    }
+
+    pub fn show(&mut self) {
+        let Some(combination) = self.combinations.get(self.current) else {
+            return;
+        };
+        let url_op = combination.url.clone();
         println!("Show combination {} url {}", self.current, url_op.clone().unwrap_or("None".to_owned()));
         if let Some(url) = url_op {
             let key = "BROWSER";
             match env::var(key) {
                 Ok(val) => {
-                    Command::new(val)
+                    let result = Command::new(&val)
                         .arg("--new-tab")
                         .arg(url)
                         .spawn()
-                        .expect("Failed to start process")
-                        .wait()
-                        .expect("Failed to wait on child");
+                        .and_then(|mut child| child.wait());
+                    if let Err(e) = result {
+                        self.errors.push(format!("Failed to start {}: {}", val, e));
+                    }
                 }
                 Err(e) => eprintln!("Couldn't find env variable {key}: {e}"),
             }
@@ -195,78 +1016,194 @@ impl Model {
     }
 }
 
-impl Default for Model {
-    fn default() -> Self {
-        let data = combination::load_data(PATH);
-        if data.is_err() {
-            eprintln!("ERROR {}", data.unwrap_err());
-            process::exit(1);
-        }
-        let data = data.unwrap();
-        let mut s = Self {
-            number: 1,
-            current: 0,
-            distance_selection: Some(DistanceSelection::All),
-            defence_selection: Some(DefenceSelection::All),
-            faint_selection: Some(FaintSelection::All),
-            body_selection: Some(BodySelection::All),
-            combinations: filter(
-                &data,
-                DistanceSelection::All,
-                DefenceSelection::All,
-                FaintSelection::All,
-                BodySelection::All,
-            ),
-            data,
-            scrollable_id: Id::unique(),
-        };
-        s.reset_in_random_order();
-        s
-    }
+fn audio_backend() -> Rc<dyn AudioBackend> {
+    let speaker = CommandLine::new(&env::var(TTS_KEY).unwrap_or_default());
+    let player = CommandLine::new(&env::var(PLAYER_KEY).unwrap_or(DEFAULT_PLAYER.to_owned()));
+    Rc::new(ProcessBackend::new(speaker, player))
 }
 
-fn filter(
-    data: &Vec<Rc<Combination>>,
-    distance: DistanceSelection,
-    defence: DefenceSelection,
-    faint: FaintSelection,
-    body: BodySelection,
-) -> Vec<Rc<Combination>> {
-    let mut result: Vec<Rc<Combination>> = vec![];
-    for c in data {
-        if filter_combination(c, distance, defence, faint, body) {
-            result.push(c.clone());
-        }
-    }
-    result
+// Every round, the first one too, starts with the next combination.
+fn starts_round(events: &[TimerEvent]) -> bool {
+    events.iter().any(|e| matches!(e, TimerEvent::RoundStarted(_)))
 }
 
-fn filter_combination(
-    com: &Combination,
-    distance: DistanceSelection,
-    defence: DefenceSelection,
-    faint: FaintSelection,
-    body: BodySelection,
-) -> bool {
-    let distance_result = match com.distance {
-        Distance::Long => !matches!(distance, DistanceSelection::Short),
-        Distance::Short => !matches!(distance, DistanceSelection::Long),
+fn load_options() -> LoadOptions {
+    let consistency = if env::var(STRICT_KEY).is_ok() {
+        Consistency::Strict
+    } else {
+        Consistency::Warn
     };
+    LoadOptions {
+        consistency,
+        skip_invalid: env::var(SKIP_INVALID_KEY).is_ok(),
+    }
+}
 
-    let defense_result = match com.defense {
-        Defense::Yes => !matches!(defence, DefenceSelection::No),
-        Defense::No => !matches!(defence, DefenceSelection::Yes),
-    };
+// Errors first, then the flags that don't match the notation.
+fn messages(loaded: &Loaded) -> Vec<String> {
+    let errors = loaded.errors.iter().map(|e| e.to_string());
+    let warnings = loaded.warnings.iter().map(|w| format!("WARNING {}", w));
+    errors.chain(warnings).collect()
+}
 
-    let faint_result = match com.faint {
-        Faint::Yes => !matches!(faint, FaintSelection::No),
-        Faint::No => !matches!(faint, FaintSelection::Yes),
-    };
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::NullBackend;
+    use crate::combination::Body;
+    use crate::testing::TempDir;
+    use std::fs;
 
-    let body_result = match com.body {
-        Body::Yes => !matches!(body, BodySelection::No),
-        Body::No => !matches!(body, BodySelection::Yes),
-    };
+    const LIBRARY: &str = "./combinations.txt";
+
+    fn model(dir: &TempDir, library: &Path, state: State) -> Model {
+        model_with_audio(dir, library, state, Rc::new(NullBackend::default()))
+    }
+
+    fn model_with_audio(
+        dir: &TempDir,
+        library: &Path,
+        state: State,
+        audio: Rc<NullBackend>,
+    ) -> Model {
+        let config = Config {
+            libraries: vec![library.to_owned()],
+            seed: None,
+        };
+        let paths = Paths {
+            state: dir.join("state.toml"),
+            practice: dir.join("practice.toml"),
+            presets: dir.join("presets.toml"),
+            journal: dir.join("journal.jsonl"),
+            bindings: dir.join("bindings.txt"),
+        };
+        Model::with(config, state, paths, audio)
+    }
+
+    fn descriptions(model: &Model) -> Vec<String> {
+        model.combinations().iter().map(|c| c.description.clone()).collect()
+    }
+
+    #[test]
+    fn test_same_seed_same_order() {
+        let dir = TempDir::new("model_same_seed_same_order");
+        let seeded = |seed| State {
+            seed,
+            ..State::default()
+        };
+        let first = model(&dir, Path::new(LIBRARY), seeded(42));
+        let second = model(&dir, Path::new(LIBRARY), seeded(42));
+        assert_eq!(descriptions(&first), descriptions(&second));
+        let mut other = model(&dir, Path::new(LIBRARY), seeded(7));
+        assert_ne!(descriptions(&other), descriptions(&first));
+        other.set_seed(42);
+        assert_eq!(descriptions(&other), descriptions(&first));
+    }
+
+    #[test]
+    fn test_failed_reload_keeps_data() {
+        let dir = TempDir::new("model_failed_reload_keeps_data");
+        let library = dir.join("basics.txt");
+        fs::write(&library, "1-2; Long; No; No; No;\n1-1-2; Long; No; No; No;\n").unwrap();
+        let mut model = model(&dir, &library, State::default());
+        let loaded = descriptions(&model);
+        assert_eq!(loaded.len(), 2);
+        fs::write(&library, "1-2; XXX; No; No; No;\n").unwrap();
+        model.reload();
+        assert_eq!(descriptions(&model), loaded);
+        assert_eq!(model.errors().len(), 1);
+    }
+
+    #[test]
+    fn test_restore_state() {
+        let dir = TempDir::new("model_restore_state");
+        let state = State {
+            query: "body".to_owned(),
+            search: "1-2".to_owned(),
+            order: Order::InOrder,
+            current: 1,
+            number: 5,
+            ..State::default()
+        };
+        let model = model(&dir, Path::new(LIBRARY), state.clone());
+        assert_eq!((model.query_text(), model.search_text()), ("body", "1-2"));
+        assert_eq!((model.current(), model.number()), (1, "5.".to_owned()));
+        assert!(model.combinations().len() > 1);
+        assert!(
+            model
+                .combinations()
+                .iter()
+                .all(|c| c.body == Body::Yes && search::matches("1-2", c, Mode::Substring))
+        );
+
+        let beyond = State {
+            current: model.combinations().len(),
+            ..state
+        };
+        let model = self::model(&dir, Path::new(LIBRARY), beyond);
+        assert_eq!((model.current(), model.number()), (0, "1.".to_owned()));
+    }
+
+    #[test]
+    fn test_announce() {
+        let dir = TempDir::new("model_announce");
+        let audio = Rc::new(NullBackend::default());
+        let state = State {
+            order: Order::InOrder,
+            ..State::default()
+        };
+        let mut model = model_with_audio(&dir, Path::new(LIBRARY), state, audio.clone());
+        model.next();
+        model.moved();
+        assert!(audio.spoken().is_empty());
+        model.set_speech(true);
+        model.set_search("1-1-2b".to_owned());
+        assert!(audio.spoken().is_empty());
+        model.next();
+        model.moved();
+        let callout = model.combinations()[model.current()].notation().unwrap().callout();
+        assert_eq!(audio.spoken(), vec![callout]);
+    }
 
-    distance_result && defense_result && faint_result && body_result
+    #[test]
+    fn test_play_cues() {
+        let dir = TempDir::new("model_play_cues");
+        let audio = Rc::new(NullBackend::default());
+        let mut model = model_with_audio(&dir, Path::new(LIBRARY), State::default(), audio.clone());
+        let events = [TimerEvent::RoundStarted(1), TimerEvent::Finished];
+        model.play_cues(&events);
+        assert_eq!(audio.played(), vec![PathBuf::from("./sounds/round_start.wav")]);
+        model.set_sound(false);
+        model.play_cues(&events);
+        assert_eq!(audio.played().len(), 1);
+    }
+
+    #[test]
+    fn test_callouts() {
+        let dir = TempDir::new("model_callouts");
+        let state = State {
+            order: Order::InOrder,
+            ..State::default()
+        };
+        let mut model = model(&dir, Path::new(LIBRARY), state);
+        model.set_pace(Pace::Fixed(Seconds(3)));
+        let start = Instant::now();
+        assert_eq!(model.start_timer(), vec![TimerEvent::RoundStarted(1)]);
+        assert_eq!(model.current(), 1);
+        model.tick(start);
+        model.tick(start + Duration::from_secs(2));
+        assert_eq!(model.current(), 1);
+        model.tick(start + Duration::from_secs(3));
+        assert_eq!(model.current(), 2);
+        model.tick(start + Duration::from_secs(5));
+        assert_eq!(model.current(), 2);
+        model.tick(start + Duration::from_secs(6));
+        assert_eq!(model.current(), 3);
+
+        model.stop_timer();
+        model.set_pace(Pace::Off);
+        model.start_timer();
+        model.tick(start + Duration::from_secs(60));
+        assert_eq!(model.current(), 3);
+    }
 }
@@ -0,0 +1,625 @@
+use std::{error, fmt};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Left,
+    Right,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Punch {
+    pub number: u8,
+    pub body: bool,
+    pub feint: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Movement {
+    Slip(Option<Side>),
+    Roll(Option<Side>),
+    LeanBack,
+    LeanOut,
+    Block,
+    Catch,
+    Step(Option<Side>),
+    StepBack,
+    SideStep(Option<Side>),
+    ShuffleStep,
+    Pivot(Option<Side>),
+    Shift,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Token {
+    Punch(Punch),
+    Move(Movement),
+    Together(Vec<Token>),
+    Text(String),
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Notation {
+    pub tokens: Vec<Token>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NotationError {
+    Empty,
+    MissingToken(String),
+    InvalidPunch(String),
+    InvalidRepeat(String),
+}
+
+const SEQUENCE: char = '-';
+const TOGETHER: char = '+';
+const JOIN: char = '_';
+const BODY: char = 'b';
+const FEINT: char = 'f';
+const REPEAT: char = 'x';
+const WITHOUT: &str = "without";
+const MIN_PUNCH: u8 = 1;
+const MAX_PUNCH: u8 = 6;
+const MAX_REPEAT: usize = 10;
+
+// Longest phrases first, so that "step back" wins over "step".
+const MOVEMENTS: [(&[&str], Movement); 29] = [
+    (
+        &["side", "step", "left"],
+        Movement::SideStep(Some(Side::Left)),
+    ),
+    (
+        &["side", "step", "right"],
+        Movement::SideStep(Some(Side::Right)),
+    ),
+    (
+        &["left", "side", "step"],
+        Movement::SideStep(Some(Side::Left)),
+    ),
+    (
+        &["right", "side", "step"],
+        Movement::SideStep(Some(Side::Right)),
+    ),
+    (&["side", "step"], Movement::SideStep(None)),
+    (&["slip", "left"], Movement::Slip(Some(Side::Left))),
+    (&["slip", "right"], Movement::Slip(Some(Side::Right))),
+    (&["roll", "left"], Movement::Roll(Some(Side::Left))),
+    (&["roll", "right"], Movement::Roll(Some(Side::Right))),
+    (&["lean", "back"], Movement::LeanBack),
+    (&["lean", "out"], Movement::LeanOut),
+    (&["step", "back"], Movement::StepBack),
+    (&["step", "left"], Movement::Step(Some(Side::Left))),
+    (&["step", "right"], Movement::Step(Some(Side::Right))),
+    (&["shuffle", "step"], Movement::ShuffleStep),
+    (&["pivot", "left"], Movement::Pivot(Some(Side::Left))),
+    (&["pivot", "right"], Movement::Pivot(Some(Side::Right))),
+    (&["stepback"], Movement::StepBack),
+    (&["sidestep"], Movement::SideStep(None)),
+    (&["slip"], Movement::Slip(None)),
+    (&["roll"], Movement::Roll(None)),
+    (&["block"], Movement::Block),
+    (&["catch"], Movement::Catch),
+    (&["step"], Movement::Step(None)),
+    (&["pivot"], Movement::Pivot(None)),
+    (&["shift"], Movement::Shift),
+    (&["leanback"], Movement::LeanBack),
+    (&["parry"], Movement::Catch),
+    (&["duck"], Movement::Roll(None)),
+];
+
+impl Movement {
+    pub fn is_defensive(&self) -> bool {
+        !matches!(
+            self,
+            Movement::Step(_) | Movement::ShuffleStep | Movement::Shift
+        )
+    }
+}
+
+impl Notation {
+    pub fn punches(&self) -> Vec<&Punch> {
+        let mut result = vec![];
+        collect_punches(&self.tokens, &mut result);
+        result
+    }
+
+    pub fn movements(&self) -> Vec<&Movement> {
+        let mut result = vec![];
+        collect_movements(&self.tokens, &mut result);
+        result
+    }
+
+    pub fn punch_count(&self) -> usize {
+        self.punches().len()
+    }
+
+    pub fn has_body(&self) -> bool {
+        self.punches().iter().any(|p| p.body)
+    }
+
+    pub fn has_feint(&self) -> bool {
+        self.punches().iter().any(|p| p.feint)
+    }
+
+    pub fn has_defense(&self) -> bool {
+        self.movements().iter().any(|m| m.is_defensive())
+    }
+
+    pub fn callout(&self) -> String {
+        let words: Vec<String> = self.tokens.iter().map(Token::callout).collect();
+        words.join(", ")
+    }
+}
+
+impl Punch {
+    pub fn name(&self) -> &'static str {
+        match self.number {
+            1 => "jab",
+            2 => "cross",
+            3 => "lead hook",
+            4 => "rear hook",
+            5 => "lead uppercut",
+            _ => "rear uppercut",
+        }
+    }
+}
+
+impl Token {
+    pub fn callout(&self) -> String {
+        match self {
+            Token::Punch(punch) => {
+                let mut words = vec![];
+                if punch.feint {
+                    words.push("feint");
+                }
+                if punch.body {
+                    words.push("body");
+                }
+                words.push(punch.name());
+                words.join(" ")
+            }
+            Token::Move(movement) => movement.to_string().replace(JOIN, " "),
+            Token::Together(group) => {
+                let words: Vec<String> = group.iter().map(Token::callout).collect();
+                words.join(" and ")
+            }
+            Token::Text(text) => text.replace(JOIN, " "),
+        }
+    }
+}
+
+fn collect_punches<'a>(tokens: &'a [Token], result: &mut Vec<&'a Punch>) {
+    for token in tokens {
+        match token {
+            Token::Punch(punch) => result.push(punch),
+            Token::Together(group) => collect_punches(group, result),
+            Token::Move(_) | Token::Text(_) => {}
+        }
+    }
+}
+
+fn collect_movements<'a>(tokens: &'a [Token], result: &mut Vec<&'a Movement>) {
+    for token in tokens {
+        match token {
+            Token::Move(movement) => result.push(movement),
+            Token::Together(group) => collect_movements(group, result),
+            Token::Punch(_) | Token::Text(_) => {}
+        }
+    }
+}
+
+// A lexeme is a piece of a word between separators together with the
+// separator that preceded it (None at the start of a whitespace delimited word).
+struct Lexeme<'a> {
+    separator: Option<char>,
+    text: &'a str,
+}
+
+pub fn parse(description: &str) -> Result<Notation, NotationError> {
+    let description = description.trim();
+    if description.is_empty() {
+        return Err(NotationError::Empty);
+    }
+    let mut tokens: Vec<Token> = vec![];
+    for word in description.split_whitespace() {
+        let lexemes = split_word(word)?;
+        parse_word(&lexemes, &mut tokens)?;
+    }
+    Ok(Notation { tokens })
+}
+
+fn split_word(word: &str) -> Result<Vec<Lexeme<'_>>, NotationError> {
+    let mut lexemes = vec![];
+    let mut separator = None;
+    let mut start = 0;
+    for (index, c) in word.char_indices() {
+        if c == SEQUENCE || c == TOGETHER || c == JOIN {
+            if index == start {
+                return Err(NotationError::MissingToken(word.to_owned()));
+            }
+            lexemes.push(Lexeme {
+                separator,
+                text: &word[start..index],
+            });
+            separator = Some(c);
+            start = index + c.len_utf8();
+        }
+    }
+    if start == word.len() {
+        return Err(NotationError::MissingToken(word.to_owned()));
+    }
+    lexemes.push(Lexeme {
+        separator,
+        text: &word[start..],
+    });
+    Ok(lexemes)
+}
+
+fn parse_word(lexemes: &[Lexeme], tokens: &mut Vec<Token>) -> Result<(), NotationError> {
+    // Text continues only across '_', anything else starts a new token.
+    let mut text_open = false;
+    let mut index = 0;
+    while index < lexemes.len() {
+        let separator = lexemes[index].separator;
+        // "without_slip" names a movement that is not performed, keep it as text.
+        let negated = text_open
+            && separator == Some(JOIN)
+            && lexemes[index - 1].text.eq_ignore_ascii_case(WITHOUT);
+        let (parsed, consumed) = match match_movement(&lexemes[index..]) {
+            Some((_, consumed)) if negated => {
+                if let Some(Token::Text(previous)) = last_token(tokens) {
+                    for lexeme in &lexemes[index..index + consumed] {
+                        previous.push(JOIN);
+                        previous.push_str(lexeme.text);
+                    }
+                }
+                index += consumed;
+                continue;
+            }
+            Some((movement, consumed)) => (vec![Token::Move(movement)], consumed),
+            None => match parse_punch(lexemes[index].text)? {
+                Some(punches) => (punches, 1),
+                None => {
+                    let text = lexemes[index].text;
+                    if text_open && separator == Some(JOIN) {
+                        if let Some(Token::Text(previous)) = last_token(tokens) {
+                            previous.push(JOIN);
+                            previous.push_str(text);
+                        }
+                    } else {
+                        push(tokens, separator, Token::Text(text.to_owned()));
+                    }
+                    text_open = true;
+                    index += 1;
+                    continue;
+                }
+            },
+        };
+        for (i, token) in parsed.into_iter().enumerate() {
+            push(tokens, if i == 0 { separator } else { None }, token);
+        }
+        text_open = false;
+        index += consumed;
+    }
+    Ok(())
+}
+
+fn last_token(tokens: &mut [Token]) -> Option<&mut Token> {
+    match tokens.last_mut() {
+        Some(Token::Together(group)) => group.last_mut(),
+        other => other,
+    }
+}
+
+fn push(tokens: &mut Vec<Token>, separator: Option<char>, token: Token) {
+    if separator != Some(TOGETHER) {
+        tokens.push(token);
+        return;
+    }
+    match tokens.pop() {
+        Some(Token::Together(mut group)) => {
+            group.push(token);
+            tokens.push(Token::Together(group));
+        }
+        Some(previous) => tokens.push(Token::Together(vec![previous, token])),
+        None => tokens.push(token),
+    }
+}
+
+fn match_movement(lexemes: &[Lexeme]) -> Option<(Movement, usize)> {
+    for (phrase, movement) in MOVEMENTS.iter() {
+        if phrase.len() > lexemes.len() {
+            continue;
+        }
+        let matched = phrase
+            .iter()
+            .zip(lexemes)
+            .enumerate()
+            .all(|(i, (word, lexeme))| {
+                lexeme.text.eq_ignore_ascii_case(word)
+                    && (i == 0 || lexeme.separator != Some(TOGETHER))
+            });
+        if matched {
+            return Some((*movement, phrase.len()));
+        }
+    }
+    None
+}
+
+// Accepts [<count>x][f]<number>[b], e.g. "3", "3b", "f1b" or "2x1b".
+fn parse_punch(text: &str) -> Result<Option<Vec<Token>>, NotationError> {
+    let lower = text.to_ascii_lowercase();
+    let (count, rest) = match lower.split_once(REPEAT) {
+        Some((count, rest)) if !count.is_empty() && count.chars().all(|c| c.is_ascii_digit()) => {
+            (count.parse::<usize>().ok(), rest)
+        }
+        _ => (Some(1), lower.as_str()),
+    };
+    let (feint, rest) = match rest.strip_prefix(FEINT) {
+        Some(rest) => (true, rest),
+        None => (false, rest),
+    };
+    let (body, rest) = match rest.strip_suffix(BODY) {
+        Some(rest) => (true, rest),
+        None => (false, rest),
+    };
+    if rest.is_empty() || !rest.chars().all(|c| c.is_ascii_digit()) {
+        return Ok(None);
+    }
+    let number = match rest.parse::<u8>() {
+        Ok(number) if (MIN_PUNCH..=MAX_PUNCH).contains(&number) => number,
+        _ => return Err(NotationError::InvalidPunch(text.to_owned())),
+    };
+    let count = match count {
+        Some(count) if (1..=MAX_REPEAT).contains(&count) => count,
+        _ => return Err(NotationError::InvalidRepeat(text.to_owned())),
+    };
+    let punch = Punch {
+        number,
+        body,
+        feint,
+    };
+    Ok(Some(vec![Token::Punch(punch); count]))
+}
+
+impl fmt::Display for Side {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Side::Left => write!(f, "left"),
+            Side::Right => write!(f, "right"),
+        }
+    }
+}
+
+impl fmt::Display for Punch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.feint {
+            write!(f, "{}", FEINT)?;
+        }
+        write!(f, "{}", self.number)?;
+        if self.body {
+            write!(f, "{}", BODY)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Movement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (name, side) = match self {
+            Movement::Slip(side) => ("slip", side),
+            Movement::Roll(side) => ("roll", side),
+            Movement::LeanBack => ("lean_back", &None),
+            Movement::LeanOut => ("lean_out", &None),
+            Movement::Block => ("block", &None),
+            Movement::Catch => ("catch", &None),
+            Movement::Step(side) => ("step", side),
+            Movement::StepBack => ("step_back", &None),
+            Movement::SideStep(side) => ("side_step", side),
+            Movement::ShuffleStep => ("shuffle_step", &None),
+            Movement::Pivot(side) => ("pivot", side),
+            Movement::Shift => ("shift", &None),
+        };
+        match side {
+            Some(side) => write!(f, "{}{}{}", name, JOIN, side),
+            None => write!(f, "{}", name),
+        }
+    }
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Punch(punch) => write!(f, "{}", punch),
+            Token::Move(movement) => write!(f, "{}", movement),
+            Token::Text(text) => write!(f, "{}", text),
+            Token::Together(group) => {
+                for (index, token) in group.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, "{}", TOGETHER)?;
+                    }
+                    write!(f, "{}", token)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl fmt::Display for Notation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, token) in self.tokens.iter().enumerate() {
+            if index > 0 {
+                write!(f, "{}", SEQUENCE)?;
+            }
+            write!(f, "{}", token)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for NotationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NotationError::Empty => write!(f, "Empty description"),
+            NotationError::MissingToken(word) => {
+                write!(f, "Missing token between separators in {:?}", word)
+            }
+            NotationError::InvalidPunch(punch) => write!(
+                f,
+                "Invalid punch {:?}, expected {}-{} with optional {} prefix and {} suffix",
+                punch, MIN_PUNCH, MAX_PUNCH, FEINT, BODY
+            ),
+            NotationError::InvalidRepeat(punch) => write!(
+                f,
+                "Invalid repeat count in {:?}, expected 1-{}",
+                punch, MAX_REPEAT
+            ),
+        }
+    }
+}
+
+impl error::Error for NotationError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::combination::{Consistency, LoadOptions, load_data};
+    use std::path::Path;
+
+    fn punch(number: u8) -> Token {
+        Token::Punch(Punch {
+            number,
+            body: false,
+            feint: false,
+        })
+    }
+
+    #[test]
+    fn test_parse_punches_and_movement() {
+        assert_eq!(
+            parse("1-2-3b-3-step_back-2").unwrap().tokens,
+            vec![
+                punch(1),
+                punch(2),
+                Token::Punch(Punch {
+                    number: 3,
+                    body: true,
+                    feint: false,
+                }),
+                punch(3),
+                Token::Move(Movement::StepBack),
+                punch(2),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_feint_and_repeat() {
+        let notation = parse("setup 2x1b f1b-3-2").unwrap();
+        assert_eq!(notation.tokens[0], Token::Text("setup".to_owned()));
+        assert_eq!(notation.punch_count(), 5);
+        assert!(notation.has_feint());
+        assert!(notation.has_body());
+        assert!(!notation.has_defense());
+    }
+
+    #[test]
+    fn test_parse_together() {
+        assert_eq!(
+            parse("1-2+1-1").unwrap().tokens,
+            vec![
+                punch(1),
+                Token::Together(vec![punch(2), punch(1)]),
+                punch(1),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_text_prefix_and_suffix() {
+        let notation = parse("fast_light_close_setup 1-2-1-2 for_power").unwrap();
+        assert_eq!(
+            notation.tokens.first(),
+            Some(&Token::Text("fast_light_close_setup".to_owned()))
+        );
+        assert_eq!(
+            notation.tokens.last(),
+            Some(&Token::Text("for_power".to_owned()))
+        );
+        assert_eq!(notation.punch_count(), 4);
+    }
+
+    #[test]
+    fn test_parse_movement_phrases() {
+        assert_eq!(
+            parse("1-1-2-side_step_right_with_3-2").unwrap().movements(),
+            vec![&Movement::SideStep(Some(Side::Right))]
+        );
+        assert_eq!(
+            parse("close slip-right-4b").unwrap().movements(),
+            vec![&Movement::Slip(Some(Side::Right))]
+        );
+        assert!(parse("1-2-lean_back-2").unwrap().has_defense());
+        assert_eq!(
+            parse("1-2+3b without_side_step").unwrap().tokens.last(),
+            Some(&Token::Text("without_side_step".to_owned()))
+        );
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(
+            parse("f1b-2+3-Slip_Left-4").unwrap().to_string(),
+            "f1b-2+3-slip_left-4"
+        );
+    }
+
+    #[test]
+    fn test_callout() {
+        assert_eq!(
+            parse("1-1-2-step_back-2").unwrap().callout(),
+            "jab, jab, cross, step back, cross"
+        );
+        assert_eq!(
+            parse("setup f1b-2+3b-slip_left").unwrap().callout(),
+            "setup, feint body jab, cross and body lead hook, slip left"
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(parse("  "), Err(NotationError::Empty));
+        assert_eq!(
+            parse("1--2"),
+            Err(NotationError::MissingToken("1--2".to_owned()))
+        );
+        assert_eq!(
+            parse("1-7b"),
+            Err(NotationError::InvalidPunch("7b".to_owned()))
+        );
+        assert_eq!(parse("10x1").unwrap().punch_count(), 10);
+        assert_eq!(
+            parse("1-11x2"),
+            Err(NotationError::InvalidRepeat("11x2".to_owned()))
+        );
+        assert_eq!(
+            parse("0x2"),
+            Err(NotationError::InvalidRepeat("0x2".to_owned()))
+        );
+    }
+
+    #[test]
+    fn test_parse_data() {
+        let options = LoadOptions {
+            consistency: Consistency::Warn,
+            skip_invalid: false,
+        };
+        for combination in load_data(Path::new("./combinations.txt"), options).unwrap().data {
+            assert!(
+                parse(&combination.description).is_ok(),
+                "{}",
+                combination.description
+            );
+        }
+    }
+}
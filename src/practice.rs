@@ -0,0 +1,282 @@
+use std::{
+    collections::BTreeMap,
+    fmt, io,
+    path::{Path, PathBuf},
+};
+
+use chrono::{Local, TimeZone};
+use rand::{Rng, distributions::WeightedIndex, prelude::Distribution};
+use serde::{Deserialize, Serialize};
+
+use crate::combination::Combination;
+use crate::config;
+use crate::schedule::{Rating, Review, SECONDS_PER_DAY, Schedule};
+
+const PRACTICE_FILE: &str = "practice.toml";
+const DATE_FORMAT: &str = "%Y-%m-%d";
+const DEFAULT_DIFFICULTY: u8 = 3;
+// Combinations not practiced for a month weigh as much as new ones.
+const MAX_DAYS: f64 = 30.0;
+pub const DEFAULT_PRIORITY: u8 = 3;
+pub const PRIORITIES: [u8; 5] = [1, 2, 3, 4, 5];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Entry {
+    pub priority: u8,
+    // Unix time in seconds.
+    pub last_practiced: Option<i64>,
+    // How often the combination was shown, rated or not.
+    pub times_practiced: u32,
+    pub schedule: Schedule,
+    pub reviews: Vec<Review>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Stats {
+    pub times_practiced: u32,
+    pub ratings: usize,
+    pub average: Option<f64>,
+    pub last_practiced: Option<i64>,
+}
+
+// What we know about practicing each combination, keyed by description so
+// it survives moving combinations between libraries.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Practice {
+    entries: BTreeMap<String, Entry>,
+}
+
+impl Default for Entry {
+    fn default() -> Self {
+        Entry {
+            priority: DEFAULT_PRIORITY,
+            last_practiced: None,
+            times_practiced: 0,
+            schedule: Schedule::default(),
+            reviews: vec![],
+        }
+    }
+}
+
+impl Entry {
+    pub fn stats(&self) -> Stats {
+        let total: u32 = self
+            .reviews
+            .iter()
+            .map(|r| u32::from(r.rating.score()))
+            .sum();
+        Stats {
+            times_practiced: self.times_practiced,
+            ratings: self.reviews.len(),
+            average: (!self.reviews.is_empty())
+                .then(|| f64::from(total) / self.reviews.len() as f64),
+            last_practiced: self.last_practiced,
+        }
+    }
+}
+
+impl Practice {
+    pub fn path() -> PathBuf {
+        config::config_dir().join(PRACTICE_FILE)
+    }
+
+    pub fn load(path: &Path) -> io::Result<Practice> {
+        config::load_toml(path)
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        config::save_toml(path, self)
+    }
+
+    pub fn get(&self, combination: &Combination) -> Option<&Entry> {
+        self.entries.get(&combination.description)
+    }
+
+    pub fn stats(&self, combination: &Combination) -> Stats {
+        self.entries
+            .get(&combination.description)
+            .map(Entry::stats)
+            .unwrap_or_default()
+    }
+
+    fn entry(&mut self, combination: &Combination) -> &mut Entry {
+        self.entries
+            .entry(combination.description.clone())
+            .or_default()
+    }
+
+    pub fn set_priority(&mut self, combination: &Combination, priority: u8) {
+        self.entry(combination).priority = priority;
+    }
+
+    pub fn practiced(&mut self, combination: &Combination, now: i64) {
+        let entry = self.entry(combination);
+        entry.last_practiced = Some(now);
+        entry.times_practiced += 1;
+    }
+
+    pub fn review(&mut self, combination: &Combination, rating: Rating, now: i64) {
+        let entry = self.entry(combination);
+        entry.last_practiced = Some(now);
+        entry.schedule.review(rating, now);
+        entry.reviews.push(Review { at: now, rating });
+    }
+
+    // Harder, more important and longer unpracticed combinations weigh more.
+    pub fn weight(&self, combination: &Combination, now: i64) -> f64 {
+        let entry = self.get(combination);
+        let days = entry
+            .and_then(|e| e.last_practiced)
+            .map(|last| (now - last).max(0) as f64 / SECONDS_PER_DAY as f64)
+            .unwrap_or(MAX_DAYS)
+            .min(MAX_DAYS);
+        let difficulty = combination.difficulty.unwrap_or(DEFAULT_DIFFICULTY);
+        f64::from(difficulty)
+            * f64::from(entry.map_or(DEFAULT_PRIORITY, |e| e.priority))
+            * (1.0 + days)
+    }
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Some(last) = self
+            .last_practiced
+            .and_then(|last| Local.timestamp_opt(last, 0).single())
+        else {
+            return write!(f, "never practiced");
+        };
+        write!(f, "practiced {}x, ", self.times_practiced)?;
+        match self.average {
+            Some(average) => write!(f, "{} rated, average {:.1}/3", self.ratings, average)?,
+            None => write!(f, "not rated")?,
+        }
+        write!(f, ", last {}", last.format(DATE_FORMAT))
+    }
+}
+
+// Picks an index with probability proportional to its weight, never the
+// excluded one unless it is the only choice.
+pub fn draw<R: Rng>(weights: &[f64], exclude: Option<usize>, rng: &mut R) -> Option<usize> {
+    let weights: Vec<f64> = weights
+        .iter()
+        .enumerate()
+        .map(|(i, w)| if Some(i) == exclude { 0.0 } else { w.max(0.0) })
+        .collect();
+    match WeightedIndex::new(&weights) {
+        Ok(index) => Some(index.sample(rng)),
+        Err(_) if weights.is_empty() => None,
+        Err(_) => exclude.or(Some(0)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+    use rand::{SeedableRng, rngs::StdRng};
+
+    #[test]
+    fn test_weight() {
+        let jab = Combination::from_description("1-1-2");
+        let mut hook = Combination::from_description("1-2-3b");
+        hook.difficulty = Some(5);
+        let mut practice = Practice::default();
+        let now = 100 * SECONDS_PER_DAY;
+        assert_eq!(practice.weight(&jab, now), 3.0 * 3.0 * 31.0);
+        practice.practiced(&jab, now);
+        assert_eq!(practice.weight(&jab, now), 9.0);
+        practice.set_priority(&hook, 1);
+        practice.practiced(&hook, now - 2 * SECONDS_PER_DAY);
+        assert_eq!(practice.weight(&hook, now), 5.0 * 1.0 * 3.0);
+        assert_eq!(practice.get(&hook).unwrap().priority, 1);
+    }
+
+    #[test]
+    fn test_review() {
+        let jab = Combination::from_description("1-1-2");
+        let mut practice = Practice::default();
+        practice.review(&jab, Rating::Clean, 10);
+        practice.review(&jab, Rating::Failed, 20);
+        let entry = practice.get(&jab).unwrap();
+        assert_eq!(entry.last_practiced, Some(20));
+        assert_eq!(entry.schedule.due, Some(20));
+        assert_eq!(
+            entry.reviews,
+            vec![
+                Review {
+                    at: 10,
+                    rating: Rating::Clean
+                },
+                Review {
+                    at: 20,
+                    rating: Rating::Failed
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn test_stats() {
+        let jab = Combination::from_description("1-1-2");
+        let mut practice = Practice::default();
+        let stats = practice.stats(&jab);
+        assert_eq!((stats.ratings, stats.average), (0, None));
+        assert_eq!(stats.to_string(), "never practiced");
+        let now = Local
+            .with_ymd_and_hms(2024, 3, 5, 18, 30, 0)
+            .unwrap()
+            .timestamp();
+        practice.practiced(&jab, now);
+        assert_eq!(
+            practice.stats(&jab).to_string(),
+            "practiced 1x, not rated, last 2024-03-05"
+        );
+        practice.review(&jab, Rating::Clean, now);
+        practice.review(&jab, Rating::Sloppy, now);
+        practice.review(&jab, Rating::Sloppy, now);
+        practice.practiced(&jab, now);
+        let stats = practice.stats(&jab);
+        assert_eq!((stats.times_practiced, stats.ratings), (2, 3));
+        assert_eq!(
+            stats.to_string(),
+            "practiced 2x, 3 rated, average 2.3/3, last 2024-03-05"
+        );
+    }
+
+    #[test]
+    fn test_draw() {
+        let mut rng = StdRng::seed_from_u64(1);
+        assert_eq!(draw(&[], None, &mut rng), None);
+        assert_eq!(draw(&[1.0], Some(0), &mut rng), Some(0));
+        assert_eq!(draw(&[0.0, 0.0], None, &mut rng), Some(0));
+        let mut counts = [0; 3];
+        for _ in 0..1000 {
+            let index = draw(&[1.0, 0.0, 9.0], None, &mut rng).unwrap();
+            counts[index] += 1;
+        }
+        assert_eq!(counts[1], 0);
+        assert!(counts[2] > 5 * counts[0], "{:?}", counts);
+        for _ in 0..100 {
+            assert_eq!(draw(&[1.0, 1.0], Some(0), &mut rng), Some(1));
+        }
+    }
+
+    #[test]
+    fn test_load_and_save() {
+        let dir = TempDir::new("practice_load_and_save");
+        let path = dir.join(PRACTICE_FILE);
+        assert_eq!(Practice::load(&path).unwrap(), Practice::default());
+        let mut practice = Practice::default();
+        practice.set_priority(&Combination::from_description("1-2-lean_back-2"), 5);
+        practice.practiced(&Combination::from_description("1-1-2"), 1_700_000_000);
+        practice.review(
+            &Combination::from_description("slip-2-3b"),
+            Rating::Sloppy,
+            1_700_000_100,
+        );
+        practice.save(&path).unwrap();
+        assert_eq!(Practice::load(&path).unwrap(), practice);
+    }
+}
@@ -0,0 +1,145 @@
+use std::{
+    fmt, io,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config;
+use crate::tags::TagFilter;
+
+const PRESETS_FILE: &str = "presets.toml";
+
+#[allow(clippy::enum_variant_names)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Order {
+    #[default]
+    Random,
+    InOrder,
+    Weighted,
+    Due,
+}
+
+// Everything that decides which combinations come up and in what order.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Preset {
+    pub name: String,
+    pub tags: TagFilter,
+    pub query: String,
+    pub search: String,
+    pub order: Order,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Presets {
+    #[serde(default, rename = "preset")]
+    presets: Vec<Preset>,
+}
+
+impl Presets {
+    pub fn path() -> PathBuf {
+        config::config_dir().join(PRESETS_FILE)
+    }
+
+    // A missing file has no presets.
+    pub fn load(path: &Path) -> io::Result<Presets> {
+        config::load_toml(path)
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        config::save_toml(path, self)
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.presets.iter().map(|p| p.name.clone()).collect()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Preset> {
+        self.presets.iter().find(|p| p.name == name)
+    }
+
+    // A preset with the same name is replaced in place.
+    pub fn set(&mut self, preset: Preset) {
+        match self.presets.iter_mut().find(|p| p.name == preset.name) {
+            Some(existing) => *existing = preset,
+            None => self.presets.push(preset),
+        }
+    }
+
+    pub fn remove(&mut self, name: &str) {
+        self.presets.retain(|p| p.name != name);
+    }
+}
+
+impl fmt::Display for Order {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Order::Random => write!(f, "Random"),
+            Order::InOrder => write!(f, "In order"),
+            Order::Weighted => write!(f, "Weighted"),
+            Order::Due => write!(f, "Due today"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tags::TagState;
+    use crate::testing::TempDir;
+    use std::fs;
+
+    #[test]
+    fn test_set_and_remove() {
+        let mut presets = Presets::default();
+        presets.set(Preset {
+            name: "body attack".to_owned(),
+            query: "body".to_owned(),
+            ..Preset::default()
+        });
+        presets.set(Preset {
+            name: "feints only".to_owned(),
+            ..Preset::default()
+        });
+        presets.set(Preset {
+            name: "body attack".to_owned(),
+            query: "body and punches>=3".to_owned(),
+            ..Preset::default()
+        });
+        assert_eq!(presets.names(), vec!["body attack", "feints only"]);
+        assert_eq!(
+            presets.get("body attack").unwrap().query,
+            "body and punches>=3"
+        );
+        presets.remove("body attack");
+        assert_eq!(presets.names(), vec!["feints only"]);
+        assert_eq!(presets.get("body attack"), None);
+    }
+
+    #[test]
+    fn test_load_and_save() {
+        let dir = TempDir::new("presets_load_and_save");
+        let path = dir.join(PRESETS_FILE);
+        assert_eq!(Presets::load(&path).unwrap(), Presets::default());
+        let mut tags = TagFilter::default();
+        tags.set("short", TagState::Include);
+        tags.set("defense", TagState::Exclude);
+        let mut presets = Presets::default();
+        presets.set(Preset {
+            name: "inside fighting".to_owned(),
+            tags,
+            query: String::new(),
+            search: "hook".to_owned(),
+            order: Order::InOrder,
+        });
+        presets.save(&path).unwrap();
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(content.contains("order = \"in_order\""), "{}", content);
+        assert!(content.contains("short = \"include\""), "{}", content);
+        assert_eq!(Presets::load(&path).unwrap(), presets);
+        fs::write(&path, "[[preset]]\nname = 3\n").unwrap();
+        assert!(Presets::load(&path).is_err());
+    }
+}
@@ -0,0 +1,454 @@
+use std::{cmp::Ordering, error, fmt};
+
+use crate::combination::{Combination, LONG, SHORT, Stance};
+use crate::tags;
+
+// A filter expression such as
+// "distance:short and body and not defense and contains:3b" or "punches>=5".
+// Terms next to each other are and-ed, "and" binds tighter than "or".
+#[derive(Debug, Clone, PartialEq)]
+pub enum Query {
+    And(Vec<Query>),
+    Or(Vec<Query>),
+    Not(Box<Query>),
+    Tag(String),
+    Contains(String),
+    Library(String),
+    Stance(Stance),
+    Compare(Field, Operator, u64),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    Punches,
+    Difficulty,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operator {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryError {
+    pub position: usize,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Open,
+    Close,
+    Operator(Operator),
+    Word(String),
+    Quoted(String),
+}
+
+const AND: &str = "and";
+const OR: &str = "or";
+const NOT: &str = "not";
+const OPERATOR_CHARS: [char; 5] = [':', '=', '!', '<', '>'];
+// Parentheses and "not" nest this deep at most, deeper ones would overflow
+// the stack of the recursive parser.
+const MAX_DEPTH: usize = 50;
+
+impl Query {
+    pub fn all() -> Query {
+        Query::And(vec![])
+    }
+
+    pub fn and(self, other: Query) -> Query {
+        match (self, other) {
+            (Query::And(mut left), Query::And(right)) => {
+                left.extend(right);
+                Query::And(left)
+            }
+            (Query::And(mut left), right) => {
+                left.push(right);
+                Query::And(left)
+            }
+            (left, right) => Query::And(vec![left, right]),
+        }
+    }
+
+    pub fn matches(&self, combination: &Combination) -> bool {
+        match self {
+            Query::And(queries) => queries.iter().all(|q| q.matches(combination)),
+            Query::Or(queries) => queries.iter().any(|q| q.matches(combination)),
+            Query::Not(query) => !query.matches(combination),
+            Query::Tag(tag) => combination.all_tags().contains(tag),
+            Query::Contains(text) => combination.description.to_lowercase().contains(text),
+            Query::Library(library) => combination.library.eq_ignore_ascii_case(library),
+            Query::Stance(stance) => combination.stance == Some(*stance),
+            Query::Compare(field, operator, value) => {
+                let actual = match field {
+                    Field::Punches => combination.notation().ok().map(|n| n.punch_count() as u64),
+                    Field::Difficulty => combination.difficulty.map(u64::from),
+                };
+                actual.is_some_and(|actual| operator.holds(actual.cmp(value)))
+            }
+        }
+    }
+}
+
+impl Operator {
+    fn holds(&self, ordering: Ordering) -> bool {
+        match self {
+            Operator::Equal => ordering.is_eq(),
+            Operator::NotEqual => ordering.is_ne(),
+            Operator::Less => ordering.is_lt(),
+            Operator::LessOrEqual => ordering.is_le(),
+            Operator::Greater => ordering.is_gt(),
+            Operator::GreaterOrEqual => ordering.is_ge(),
+        }
+    }
+}
+
+pub fn parse(query: &str) -> Result<Query, QueryError> {
+    let tokens = lex(query)?;
+    let mut parser = Parser {
+        tokens,
+        index: 0,
+        end: query.chars().count(),
+        depth: 0,
+    };
+    if parser.tokens.is_empty() {
+        return Ok(Query::all());
+    }
+    let result = parser.or()?;
+    match parser.peek() {
+        None => Ok(result),
+        Some(_) => Err(parser.error("Unexpected \")\"")),
+    }
+}
+
+fn lex(query: &str) -> Result<Vec<(usize, Token)>, QueryError> {
+    let chars: Vec<char> = query.chars().collect();
+    let mut tokens = vec![];
+    let mut i = 0;
+    while i < chars.len() {
+        let start = i;
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+        let token = match c {
+            '(' => {
+                i += 1;
+                Token::Open
+            }
+            ')' => {
+                i += 1;
+                Token::Close
+            }
+            '"' => {
+                let end = chars[i + 1..]
+                    .iter()
+                    .position(|c| *c == '"')
+                    .ok_or(QueryError {
+                        position: start,
+                        message: "Unterminated quote".to_owned(),
+                    })?;
+                let text: String = chars[i + 1..i + 1 + end].iter().collect();
+                i += end + 2;
+                Token::Quoted(text)
+            }
+            c if OPERATOR_CHARS.contains(&c) => {
+                let next = chars.get(i + 1).copied();
+                let (operator, length) = match (c, next) {
+                    ('!', Some('=')) => (Operator::NotEqual, 2),
+                    ('<', Some('=')) => (Operator::LessOrEqual, 2),
+                    ('>', Some('=')) => (Operator::GreaterOrEqual, 2),
+                    ('<', _) => (Operator::Less, 1),
+                    ('>', _) => (Operator::Greater, 1),
+                    (':' | '=', _) => (Operator::Equal, 1),
+                    _ => {
+                        return Err(QueryError {
+                            position: start,
+                            message: format!("Unknown operator {:?}", c),
+                        });
+                    }
+                };
+                i += length;
+                Token::Operator(operator)
+            }
+            _ => {
+                while i < chars.len()
+                    && !chars[i].is_whitespace()
+                    && !OPERATOR_CHARS.contains(&chars[i])
+                    && !['(', ')', '"'].contains(&chars[i])
+                {
+                    i += 1;
+                }
+                Token::Word(chars[start..i].iter().collect())
+            }
+        };
+        tokens.push((start, token));
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    index: usize,
+    end: usize,
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.index).map(|(_, token)| token)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.peek().cloned();
+        self.index += 1;
+        token
+    }
+
+    fn position(&self) -> usize {
+        self.tokens
+            .get(self.index)
+            .map(|(position, _)| *position)
+            .unwrap_or(self.end)
+    }
+
+    fn error(&self, message: &str) -> QueryError {
+        QueryError {
+            position: self.position(),
+            message: message.to_owned(),
+        }
+    }
+
+    fn keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Word(word)) if word.eq_ignore_ascii_case(keyword))
+    }
+
+    fn nested(
+        &mut self,
+        parse: fn(&mut Parser) -> Result<Query, QueryError>,
+    ) -> Result<Query, QueryError> {
+        if self.depth == MAX_DEPTH {
+            self.index -= 1;
+            return Err(self.error("Too deeply nested"));
+        }
+        self.depth += 1;
+        let result = parse(self);
+        self.depth -= 1;
+        result
+    }
+
+    fn or(&mut self) -> Result<Query, QueryError> {
+        let mut queries = vec![self.and()?];
+        while self.keyword(OR) {
+            self.index += 1;
+            queries.push(self.and()?);
+        }
+        Ok(single(queries, Query::Or))
+    }
+
+    fn and(&mut self) -> Result<Query, QueryError> {
+        let mut queries = vec![self.not()?];
+        loop {
+            if self.keyword(AND) {
+                self.index += 1;
+            } else if self.peek().is_none()
+                || self.peek() == Some(&Token::Close)
+                || self.keyword(OR)
+            {
+                break;
+            }
+            queries.push(self.not()?);
+        }
+        Ok(single(queries, Query::And))
+    }
+
+    fn not(&mut self) -> Result<Query, QueryError> {
+        if self.keyword(NOT) {
+            self.index += 1;
+            return Ok(Query::Not(Box::new(self.nested(Parser::not)?)));
+        }
+        self.atom()
+    }
+
+    fn atom(&mut self) -> Result<Query, QueryError> {
+        let position = self.position();
+        match self.next() {
+            Some(Token::Open) => {
+                let query = self.nested(Parser::or)?;
+                match self.next() {
+                    Some(Token::Close) => Ok(query),
+                    _ => Err(QueryError {
+                        position,
+                        message: "Unclosed \"(\"".to_owned(),
+                    }),
+                }
+            }
+            Some(Token::Word(word))
+                if ![AND, OR, NOT].iter().any(|k| word.eq_ignore_ascii_case(k)) =>
+            {
+                let Some(Token::Operator(operator)) = self.peek().cloned() else {
+                    return Ok(Query::Tag(tags::normalize(&word)));
+                };
+                self.index += 1;
+                let value = match self.next() {
+                    Some(Token::Word(value) | Token::Quoted(value)) => value,
+                    _ => {
+                        self.index -= 1;
+                        return Err(self.error("Expected a value"));
+                    }
+                };
+                term(&word, operator, &value).map_err(|message| QueryError { position, message })
+            }
+            Some(Token::Quoted(text)) => Ok(Query::Contains(text.to_lowercase())),
+            _ => {
+                self.index -= 1;
+                Err(self.error("Expected a filter"))
+            }
+        }
+    }
+}
+
+fn single(mut queries: Vec<Query>, combine: fn(Vec<Query>) -> Query) -> Query {
+    if queries.len() == 1 {
+        queries.remove(0)
+    } else {
+        combine(queries)
+    }
+}
+
+fn term(key: &str, operator: Operator, value: &str) -> Result<Query, String> {
+    let key = key.to_lowercase();
+    let field = match key.as_str() {
+        "punches" => Some(Field::Punches),
+        "difficulty" => Some(Field::Difficulty),
+        _ => None,
+    };
+    if let Some(field) = field {
+        let value = value
+            .parse()
+            .map_err(|_| format!("Expected a number for {} but got {:?}", key, value))?;
+        return Ok(Query::Compare(field, operator, value));
+    }
+    if operator != Operator::Equal {
+        return Err(format!("{} can only be compared with \":\"", key));
+    }
+    let value = value.to_lowercase();
+    match key.as_str() {
+        "distance" if value == LONG || value == SHORT => Ok(Query::Tag(value)),
+        "distance" => Err(format!(
+            "Unknown distance {:?} (expected {}|{})",
+            value, LONG, SHORT
+        )),
+        "tag" => Ok(Query::Tag(tags::normalize(&value))),
+        "contains" => Ok(Query::Contains(value)),
+        "library" => Ok(Query::Library(value)),
+        "stance" => match value.as_str() {
+            "orthodox" => Ok(Query::Stance(Stance::Orthodox)),
+            "southpaw" => Ok(Query::Stance(Stance::Southpaw)),
+            _ => Err(format!(
+                "Unknown stance {:?} (expected orthodox|southpaw)",
+                value
+            )),
+        },
+        _ => Err(format!(
+            "Unknown filter {:?} (expected distance|tag|contains|library|stance|punches|difficulty)",
+            key
+        )),
+    }
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at column {}", self.message, self.position + 1)
+    }
+}
+
+impl error::Error for QueryError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn combination(description: &str) -> Combination {
+        Combination {
+            tags: vec!["counter".to_owned()],
+            ..Combination::from_description(description)
+        }
+    }
+
+    fn matching(query: &str, descriptions: &[&str]) -> Vec<String> {
+        let query = parse(query).unwrap();
+        descriptions
+            .iter()
+            .map(|d| combination(d))
+            .filter(|c| query.matches(c))
+            .map(|c| c.description)
+            .collect()
+    }
+
+    const DESCRIPTIONS: [&str; 4] = ["1-2-3b", "slip-2-3b", "1-1-2-3-2", "f1-2"];
+
+    #[test]
+    fn test_matches() {
+        assert_eq!(matching("", &DESCRIPTIONS).len(), 4);
+        assert_eq!(
+            matching("body and not defense and contains:3b", &DESCRIPTIONS),
+            vec!["1-2-3b"]
+        );
+        assert_eq!(matching("body defense", &DESCRIPTIONS), vec!["slip-2-3b"]);
+        assert_eq!(matching("punches>=5", &DESCRIPTIONS), vec!["1-1-2-3-2"]);
+        assert_eq!(
+            matching("faint or (punches < 3 and defense)", &DESCRIPTIONS),
+            vec!["slip-2-3b", "f1-2"]
+        );
+        assert_eq!(matching("distance:short", &DESCRIPTIONS).len(), 0);
+        assert_eq!(
+            matching("tag:Counter and \"1-1\"", &DESCRIPTIONS),
+            vec!["1-1-2-3-2"]
+        );
+        assert_eq!(matching("difficulty>1", &DESCRIPTIONS).len(), 0);
+        assert_eq!(matching("NOT library:basics", &DESCRIPTIONS).len(), 0);
+    }
+
+    #[test]
+    fn test_and() {
+        let query = Query::all().and(Query::Tag("body".to_owned()));
+        assert_eq!(query, Query::And(vec![Query::Tag("body".to_owned())]));
+    }
+
+    #[test]
+    fn test_errors() {
+        let error = |query: &str| parse(query).unwrap_err().to_string();
+        assert_eq!(
+            error("distance:middle"),
+            "Unknown distance \"middle\" (expected long|short) at column 1"
+        );
+        assert_eq!(
+            error("body and punches>=x"),
+            "Expected a number for punches but got \"x\" at column 10"
+        );
+        assert_eq!(error("(body or faint"), "Unclosed \"(\" at column 1");
+        assert_eq!(error("body)"), "Unexpected \")\" at column 5");
+        assert_eq!(error("body and"), "Expected a filter at column 9");
+        assert_eq!(error("contains:"), "Expected a value at column 10");
+        assert_eq!(error("\"1-2"), "Unterminated quote at column 1");
+        assert_eq!(
+            error("tag<body"),
+            "tag can only be compared with \":\" at column 1"
+        );
+        assert_eq!(error(&"(".repeat(10_000)), "Too deeply nested at column 51");
+        assert_eq!(
+            error(&"not ".repeat(10_000)),
+            "Too deeply nested at column 201"
+        );
+        let nested = format!("{}body{}", "(".repeat(MAX_DEPTH), ")".repeat(MAX_DEPTH));
+        assert_eq!(parse(&nested), Ok(Query::Tag("body".to_owned())));
+    }
+}
@@ -0,0 +1,159 @@
+use std::fmt;
+
+use chrono::{DateTime, Days, Local, TimeZone};
+use serde::{Deserialize, Serialize};
+
+pub const SECONDS_PER_DAY: i64 = 86400;
+const INITIAL_EASE: f64 = 2.5;
+const MIN_EASE: f64 = 1.3;
+const SECOND_INTERVAL: u32 = 6;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Rating {
+    Clean,
+    Sloppy,
+    Failed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Review {
+    // Unix time in seconds.
+    pub at: i64,
+    pub rating: Rating,
+}
+
+// SM-2: every clean or sloppy rep stretches the interval by the ease, which
+// sloppy reps lower. A failed rep starts over and is due again right away.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Schedule {
+    pub repetitions: u32,
+    // Days until the next rep.
+    pub interval: u32,
+    pub ease: f64,
+    pub due: Option<i64>,
+}
+
+impl Rating {
+    // Averaged in the combination stats, 3 is clean.
+    pub fn score(self) -> u8 {
+        match self {
+            Rating::Clean => 3,
+            Rating::Sloppy => 2,
+            Rating::Failed => 1,
+        }
+    }
+
+    // The SM-2 response quality from 0 to 5.
+    fn quality(self) -> f64 {
+        match self {
+            Rating::Clean => 5.0,
+            Rating::Sloppy => 3.0,
+            Rating::Failed => 1.0,
+        }
+    }
+}
+
+impl Default for Schedule {
+    fn default() -> Self {
+        Schedule {
+            repetitions: 0,
+            interval: 0,
+            ease: INITIAL_EASE,
+            due: None,
+        }
+    }
+}
+
+impl Schedule {
+    pub fn review(&mut self, rating: Rating, now: i64) {
+        let quality = rating.quality();
+        if rating == Rating::Failed {
+            self.repetitions = 0;
+            self.interval = 0;
+        } else {
+            self.interval = match self.repetitions {
+                0 => 1,
+                1 => SECOND_INTERVAL,
+                _ => (f64::from(self.interval) * self.ease).round() as u32,
+            };
+            self.repetitions += 1;
+        }
+        let miss = 5.0 - quality;
+        self.ease = (self.ease + 0.1 - miss * (0.08 + miss * 0.02)).max(MIN_EASE);
+        self.due = Some(now + i64::from(self.interval) * SECONDS_PER_DAY);
+    }
+
+    // Combinations never rated are always due.
+    pub fn is_due(&self, until: i64) -> bool {
+        self.due.is_none_or(|due| due <= until)
+    }
+}
+
+// Anything due before the next local midnight is due today.
+pub fn end_of_day(now: DateTime<Local>) -> i64 {
+    now.date_naive()
+        .checked_add_days(Days::new(1))
+        .and_then(|day| day.and_hms_opt(0, 0, 0))
+        .and_then(|midnight| Local.from_local_datetime(&midnight).earliest())
+        .map(|midnight| midnight.timestamp())
+        .unwrap_or(now.timestamp() + SECONDS_PER_DAY)
+}
+
+impl fmt::Display for Rating {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Rating::Clean => write!(f, "Clean"),
+            Rating::Sloppy => write!(f, "Sloppy"),
+            Rating::Failed => write!(f, "Failed"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_review() {
+        let mut schedule = Schedule::default();
+        assert!(schedule.is_due(0));
+        schedule.review(Rating::Clean, 0);
+        assert_eq!(
+            (schedule.interval, schedule.due),
+            (1, Some(SECONDS_PER_DAY))
+        );
+        assert!(!schedule.is_due(SECONDS_PER_DAY - 1));
+        assert!(schedule.is_due(SECONDS_PER_DAY));
+        schedule.review(Rating::Clean, 0);
+        assert_eq!(schedule.interval, SECOND_INTERVAL);
+        schedule.review(Rating::Clean, 0);
+        assert_eq!(schedule.interval, 16);
+        assert!((schedule.ease - 2.8).abs() < 1e-9);
+        schedule.review(Rating::Sloppy, 0);
+        assert_eq!(schedule.interval, 45);
+        assert!((schedule.ease - 2.66).abs() < 1e-9);
+        schedule.review(Rating::Failed, 100);
+        assert_eq!((schedule.repetitions, schedule.interval), (0, 0));
+        assert_eq!(schedule.due, Some(100));
+        schedule.review(Rating::Clean, 100);
+        assert_eq!(schedule.interval, 1);
+    }
+
+    #[test]
+    fn test_min_ease() {
+        let mut schedule = Schedule::default();
+        for _ in 0..10 {
+            schedule.review(Rating::Failed, 0);
+        }
+        assert_eq!(schedule.ease, MIN_EASE);
+    }
+
+    #[test]
+    fn test_end_of_day() {
+        let now = Local.with_ymd_and_hms(2024, 3, 5, 18, 30, 0).unwrap();
+        let midnight = Local.with_ymd_and_hms(2024, 3, 6, 0, 0, 0).unwrap();
+        assert_eq!(end_of_day(now), midnight.timestamp());
+    }
+}
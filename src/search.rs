@@ -0,0 +1,114 @@
+use std::{ops::Range, rc::Rc};
+
+use crate::combination::Combination;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Substring,
+    Fuzzy,
+}
+
+// Case is ignored and "_", "-" and " " are interchangeable, so "lean back"
+// finds "lean_back".
+fn fold(c: char) -> char {
+    match c {
+        '_' | '-' => ' ',
+        c => c.to_lowercase().next().unwrap_or(c),
+    }
+}
+
+// Byte ranges of text matched by pattern, empty for an empty pattern.
+pub fn find(pattern: &str, text: &str, mode: Mode) -> Option<Vec<Range<usize>>> {
+    let pattern: Vec<char> = pattern.trim().chars().map(fold).collect();
+    if pattern.is_empty() {
+        return Some(vec![]);
+    }
+    let chars: Vec<(usize, char)> = text.char_indices().map(|(i, c)| (i, fold(c))).collect();
+    let end = |index: usize| chars.get(index).map(|(i, _)| *i).unwrap_or(text.len());
+    match mode {
+        Mode::Substring => chars
+            .windows(pattern.len())
+            .position(|window| window.iter().map(|(_, c)| *c).eq(pattern.iter().copied()))
+            .map(|start| std::iter::once(chars[start].0..end(start + pattern.len())).collect()),
+        Mode::Fuzzy => {
+            let mut ranges: Vec<Range<usize>> = vec![];
+            let mut index = 0;
+            for p in pattern.iter().filter(|c| !c.is_whitespace()) {
+                index += chars[index..].iter().position(|(_, c)| c == p)?;
+                let range = chars[index].0..end(index + 1);
+                match ranges.last_mut() {
+                    Some(last) if last.end == range.start => last.end = range.end,
+                    _ => ranges.push(range),
+                }
+                index += 1;
+            }
+            Some(ranges)
+        }
+    }
+}
+
+pub fn matches(pattern: &str, combination: &Combination, mode: Mode) -> bool {
+    find(pattern, &combination.description, mode).is_some()
+        || combination
+            .notes
+            .as_ref()
+            .is_some_and(|notes| find(pattern, notes, mode).is_some())
+}
+
+// Searches description and notes by substring and falls back to a fuzzy
+// match when no combination contains the pattern.
+pub fn search(pattern: &str, data: &[Rc<Combination>]) -> (Mode, Vec<Rc<Combination>>) {
+    for mode in [Mode::Substring, Mode::Fuzzy] {
+        let found: Vec<Rc<Combination>> = data
+            .iter()
+            .filter(|c| matches(pattern, c, mode))
+            .cloned()
+            .collect();
+        if !found.is_empty() {
+            return (mode, found);
+        }
+    }
+    (Mode::Substring, vec![])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn combination(description: &str) -> Rc<Combination> {
+        Rc::new(Combination::from_description(description))
+    }
+
+    #[test]
+    fn test_find() {
+        assert_eq!(
+            find("Lean Back", "1-2-lean_back-2", Mode::Substring),
+            Some(std::iter::once(4..13).collect())
+        );
+        assert_eq!(find("", "1-2", Mode::Substring), Some(vec![]));
+        assert_eq!(find("3b", "1-2", Mode::Substring), None);
+        assert_eq!(
+            find("lnbk", "1-2-lean_back-2", Mode::Fuzzy),
+            Some(vec![4..5, 7..8, 9..10, 12..13])
+        );
+        assert_eq!(find("sl 2", "slip-2", Mode::Fuzzy), Some(vec![0..2, 5..6]));
+        assert_eq!(find("zz", "slip-2", Mode::Fuzzy), None);
+    }
+
+    #[test]
+    fn test_search() {
+        let data = vec![
+            combination("1-2-lean_back-2"),
+            combination("slip-2-3b"),
+            combination("1-1-2"),
+        ];
+        let (mode, found) = search("lean back", &data);
+        assert_eq!(mode, Mode::Substring);
+        assert_eq!(found.len(), 1);
+        let (mode, found) = search("sl3b", &data);
+        assert_eq!(mode, Mode::Fuzzy);
+        assert_eq!(found[0].description, "slip-2-3b");
+        assert_eq!(search("", &data).1.len(), 3);
+        assert!(search("xyz", &data).1.is_empty());
+    }
+}
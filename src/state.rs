@@ -0,0 +1,112 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use crate::config;
+use crate::presets::Order;
+use crate::tags::TagFilter;
+
+const STATE_FILE: &str = "state.toml";
+const WIDTH: f32 = 2000.0;
+const HEIGHT: f32 = 800.0;
+// Smaller windows, e.g. the 0×0 of a minimized one, are not restored.
+pub const MIN_WIDTH: f32 = 400.0;
+pub const MIN_HEIGHT: f32 = 300.0;
+
+// What is needed to resume a session: the filters, the shuffled order,
+// the position in it and the window size.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct State {
+    pub tags: TagFilter,
+    pub query: String,
+    pub search: String,
+    pub order: Order,
+    pub seed: u64,
+    pub current: usize,
+    pub number: usize,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl State {
+    pub fn path() -> PathBuf {
+        config::config_dir().join(STATE_FILE)
+    }
+
+    pub fn load(path: &Path) -> io::Result<State> {
+        let mut state: State = config::load_toml(path)?;
+        state.width = state.width.max(MIN_WIDTH);
+        state.height = state.height.max(MIN_HEIGHT);
+        Ok(state)
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        config::save_toml(path, self)
+    }
+}
+
+impl Default for State {
+    fn default() -> Self {
+        State {
+            tags: TagFilter::default(),
+            query: String::new(),
+            search: String::new(),
+            order: Order::Random,
+            seed: new_seed(),
+            current: 0,
+            number: 1,
+            width: WIDTH,
+            height: HEIGHT,
+        }
+    }
+}
+
+pub fn new_seed() -> u64 {
+    Utc::now().timestamp_millis() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tags::TagState;
+    use crate::testing::TempDir;
+    use std::fs;
+
+    #[test]
+    fn test_load_and_save() {
+        let dir = TempDir::new("state_load_and_save");
+        let path = dir.join(STATE_FILE);
+        let state = State::load(&path).unwrap();
+        assert_eq!((state.current, state.number), (0, 1));
+        assert_eq!(state.order, Order::Random);
+
+        let mut tags = TagFilter::default();
+        tags.set("body", TagState::Include);
+        let state = State {
+            tags,
+            query: "punches>=3".to_owned(),
+            search: "hook".to_owned(),
+            order: Order::InOrder,
+            seed: 42,
+            current: 7,
+            number: 12,
+            width: 1200.0,
+            height: 600.0,
+        };
+        state.save(&path).unwrap();
+        assert_eq!(State::load(&path).unwrap(), state);
+
+        // Missing keys keep their defaults.
+        fs::write(&path, "seed = 5\n").unwrap();
+        let state = State::load(&path).unwrap();
+        assert_eq!((state.seed, state.number, state.width), (5, 1, WIDTH));
+        fs::write(&path, "width = 0.0\nheight = 0.0\n").unwrap();
+        let state = State::load(&path).unwrap();
+        assert_eq!((state.width, state.height), (MIN_WIDTH, MIN_HEIGHT));
+    }
+}
@@ -0,0 +1,135 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    rc::Rc,
+};
+
+use crate::combination::{Body, Combination, Defense, Faint, yes_no};
+
+const TRANSITION: &str = "→";
+
+pub type Count = (String, usize);
+
+// An overview of a library to spot gaps, e.g. hardly any uppercut leads.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Dashboard {
+    pub total: usize,
+    // Each field and how many combinations have each value.
+    pub fields: Vec<(&'static str, Vec<Count>)>,
+    // Combinations by number of punches.
+    pub lengths: BTreeMap<usize, usize>,
+    // Descriptions that are not valid notation.
+    pub unparsed: usize,
+    // Most frequent first.
+    pub punches: Vec<Count>,
+    pub leads: Vec<Count>,
+    pub transitions: Vec<Count>,
+}
+
+pub fn dashboard(data: &[Rc<Combination>]) -> Dashboard {
+    let mut distance = HashMap::new();
+    let mut defense = HashMap::new();
+    let mut faint = HashMap::new();
+    let mut body = HashMap::new();
+    let mut lengths = BTreeMap::new();
+    let mut unparsed = 0;
+    let mut punches = HashMap::new();
+    let mut leads = HashMap::new();
+    let mut transitions = HashMap::new();
+    for combination in data {
+        *distance
+            .entry(combination.distance.to_string())
+            .or_default() += 1;
+        *defense
+            .entry(yes_no(combination.defense == Defense::Yes).to_owned())
+            .or_default() += 1;
+        *faint
+            .entry(yes_no(combination.faint == Faint::Yes).to_owned())
+            .or_default() += 1;
+        *body
+            .entry(yes_no(combination.body == Body::Yes).to_owned())
+            .or_default() += 1;
+        let Ok(notation) = combination.notation() else {
+            unparsed += 1;
+            continue;
+        };
+        let thrown = notation.punches();
+        *lengths.entry(thrown.len()).or_default() += 1;
+        for punch in &thrown {
+            *punches
+                .entry(format!("{} {}", punch, punch.name()))
+                .or_default() += 1;
+        }
+        if let Some(lead) = thrown.first() {
+            *leads
+                .entry(format!("{} {}", lead, lead.name()))
+                .or_default() += 1;
+        }
+        for pair in thrown.windows(2) {
+            *transitions
+                .entry(format!("{}{}{}", pair[0], TRANSITION, pair[1]))
+                .or_default() += 1;
+        }
+    }
+    Dashboard {
+        total: data.len(),
+        fields: vec![
+            ("Distance", ranked(distance)),
+            ("Defense", ranked(defense)),
+            ("Faint", ranked(faint)),
+            ("Body", ranked(body)),
+        ],
+        lengths,
+        unparsed,
+        punches: ranked(punches),
+        leads: ranked(leads),
+        transitions: ranked(transitions),
+    }
+}
+
+// Highest count first, ties by name.
+fn ranked(counts: HashMap<String, usize>) -> Vec<Count> {
+    let mut ranked: Vec<Count> = counts.into_iter().collect();
+    ranked.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    ranked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn combination(description: &str) -> Rc<Combination> {
+        Rc::new(Combination::from_description(description))
+    }
+
+    #[test]
+    fn test_dashboard() {
+        let data = vec![
+            combination("1-2-3b"),
+            combination("2-3-2"),
+            combination("slip-2-3"),
+            combination("1-1-2"),
+            combination("1-2-7"),
+        ];
+        let dashboard = dashboard(&data);
+        assert_eq!(dashboard.total, 5);
+        assert_eq!(dashboard.unparsed, 1);
+        assert_eq!(dashboard.lengths, BTreeMap::from([(2, 1), (3, 3)]));
+        assert_eq!(
+            dashboard.fields[1],
+            ("Defense", vec![("No".to_owned(), 4), ("Yes".to_owned(), 1)])
+        );
+        assert_eq!(dashboard.punches[0], ("2 cross".to_owned(), 5));
+        assert_eq!(
+            dashboard.leads,
+            vec![("1 jab".to_owned(), 2), ("2 cross".to_owned(), 2)]
+        );
+        assert_eq!(
+            &dashboard.transitions[..3],
+            &[
+                ("1→2".to_owned(), 2),
+                ("2→3".to_owned(), 2),
+                ("1→1".to_owned(), 1)
+            ]
+        );
+    }
+}
@@ -0,0 +1,141 @@
+use std::{collections::BTreeMap, fmt};
+
+use serde::{Deserialize, Serialize};
+
+use crate::query::Query;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TagState {
+    Any,
+    Include,
+    Exclude,
+}
+
+// Combinations must carry every included tag and none of the excluded ones.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct TagFilter {
+    states: BTreeMap<String, TagState>,
+}
+
+impl TagState {
+    pub fn cycle(self) -> Self {
+        match self {
+            TagState::Any => TagState::Include,
+            TagState::Include => TagState::Exclude,
+            TagState::Exclude => TagState::Any,
+        }
+    }
+}
+
+impl TagFilter {
+    pub fn get(&self, tag: &str) -> TagState {
+        self.states
+            .get(&normalize(tag))
+            .copied()
+            .unwrap_or(TagState::Any)
+    }
+
+    pub fn set(&mut self, tag: &str, state: TagState) {
+        let tag = normalize(tag);
+        if state == TagState::Any {
+            self.states.remove(&tag);
+        } else {
+            self.states.insert(tag, state);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.states.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.states.is_empty()
+    }
+
+    pub fn query(&self) -> Query {
+        Query::And(
+            self.states
+                .iter()
+                .filter_map(|(tag, state)| {
+                    let query = Query::Tag(tag.clone());
+                    match state {
+                        TagState::Any => None,
+                        TagState::Include => Some(query),
+                        TagState::Exclude => Some(Query::Not(Box::new(query))),
+                    }
+                })
+                .collect(),
+        )
+    }
+}
+
+impl fmt::Display for TagFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let tags: Vec<String> = self
+            .states
+            .iter()
+            .filter_map(|(tag, state)| match state {
+                TagState::Any => None,
+                TagState::Include => Some(format!("+{}", tag)),
+                TagState::Exclude => Some(format!("-{}", tag)),
+            })
+            .collect();
+        write!(f, "{}", tags.join(" "))
+    }
+}
+
+pub fn normalize(tag: &str) -> String {
+    tag.trim().to_lowercase().replace(' ', "_")
+}
+
+// Tags are separated by commas, empty ones are dropped.
+pub fn parse(tags: &str) -> Vec<String> {
+    tags.split(',')
+        .map(normalize)
+        .filter(|tag| !tag.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::combination::Combination;
+
+    fn combination(description: &str, tags: &str) -> Combination {
+        Combination {
+            tags: parse(tags),
+            ..Combination::from_description(description)
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            parse(" Counter, ,power Shot"),
+            vec!["counter", "power_shot"]
+        );
+        assert!(parse("").is_empty());
+    }
+
+    #[test]
+    fn test_filter() {
+        let counter = combination("slip-2-3b", "counter, southpaw");
+        let jab = combination("1-1-2", "speed");
+        let mut filter = TagFilter::default();
+        assert!(filter.query().matches(&counter) && filter.query().matches(&jab));
+        filter.set("Counter", TagState::Include);
+        assert_eq!(filter.get(" COUNTER "), TagState::Include);
+        assert!(filter.query().matches(&counter) && !filter.query().matches(&jab));
+        filter.set("counter", TagState::Any);
+        filter.set("body", TagState::Exclude);
+        assert!(!filter.query().matches(&counter) && filter.query().matches(&jab));
+        filter.set("long", TagState::Include);
+        assert!(filter.query().matches(&jab));
+        assert_eq!(filter.to_string(), "-body +long");
+        filter.clear();
+        assert!(filter.is_empty());
+        assert_eq!(TagState::Exclude.cycle(), TagState::Any);
+    }
+}
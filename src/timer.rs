@@ -0,0 +1,360 @@
+use rand::Rng;
+use std::{fmt, time::Duration};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    Idle,
+    Work,
+    Rest,
+    Finished,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimerEvent {
+    RoundStarted(usize),
+    Warning(usize),
+    RoundEnded(usize),
+    Finished,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Seconds(pub u64);
+
+pub const ROUND_LENGTHS: [Seconds; 7] = [
+    Seconds(30),
+    Seconds(60),
+    Seconds(90),
+    Seconds(120),
+    Seconds(180),
+    Seconds(240),
+    Seconds(300),
+];
+pub const REST_LENGTHS: [Seconds; 6] = [
+    Seconds(0),
+    Seconds(15),
+    Seconds(30),
+    Seconds(45),
+    Seconds(60),
+    Seconds(90),
+];
+const WARNING: Duration = Duration::from_secs(10);
+
+pub const ROUND_COUNTS: [usize; 12] = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pace {
+    Off,
+    Fixed(Seconds),
+    Random(Seconds, Seconds),
+    PerPunch { base: Seconds, millis: u64 },
+}
+
+pub const PACES: [Pace; 9] = [
+    Pace::Off,
+    Pace::Fixed(Seconds(3)),
+    Pace::Fixed(Seconds(5)),
+    Pace::Fixed(Seconds(8)),
+    Pace::Fixed(Seconds(12)),
+    Pace::Random(Seconds(3), Seconds(6)),
+    Pace::Random(Seconds(5), Seconds(10)),
+    Pace::PerPunch {
+        base: Seconds(2),
+        millis: 600,
+    },
+    Pace::PerPunch {
+        base: Seconds(3),
+        millis: 1000,
+    },
+];
+
+impl Pace {
+    pub fn interval(&self, punches: usize, rng: &mut impl Rng) -> Option<Duration> {
+        match *self {
+            Pace::Off => None,
+            Pace::Fixed(seconds) => Some(seconds.into()),
+            Pace::Random(min, max) => Some(Duration::from_secs(rng.gen_range(min.0..=max.0))),
+            Pace::PerPunch { base, millis } => {
+                Some(Duration::from(base) + Duration::from_millis(millis * punches as u64))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RoundTimer {
+    round_length: Seconds,
+    rest_length: Seconds,
+    rounds: usize,
+    phase: Phase,
+    round: usize,
+    remaining: Duration,
+    running: bool,
+}
+
+impl RoundTimer {
+    pub fn new(round_length: Seconds, rest_length: Seconds, rounds: usize) -> RoundTimer {
+        RoundTimer {
+            round_length,
+            rest_length,
+            rounds,
+            phase: Phase::Idle,
+            round: 0,
+            remaining: round_length.into(),
+            running: false,
+        }
+    }
+
+    pub fn round_length(&self) -> Seconds {
+        self.round_length
+    }
+
+    pub fn set_round_length(&mut self, round_length: Seconds) {
+        self.round_length = round_length;
+        if self.phase == Phase::Idle {
+            self.remaining = round_length.into();
+        }
+    }
+
+    pub fn rest_length(&self) -> Seconds {
+        self.rest_length
+    }
+
+    pub fn set_rest_length(&mut self, rest_length: Seconds) {
+        self.rest_length = rest_length;
+    }
+
+    pub fn rounds(&self) -> usize {
+        self.rounds
+    }
+
+    pub fn set_rounds(&mut self, rounds: usize) {
+        self.rounds = rounds.max(self.round).max(1);
+    }
+
+    pub fn phase(&self) -> Phase {
+        self.phase
+    }
+
+    pub fn round(&self) -> usize {
+        self.round
+    }
+
+    pub fn remaining(&self) -> Duration {
+        self.remaining
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
+
+    pub fn start(&mut self) -> Vec<TimerEvent> {
+        self.running = true;
+        match self.phase {
+            Phase::Idle | Phase::Finished => {
+                self.start_round(1);
+                vec![TimerEvent::RoundStarted(1)]
+            }
+            Phase::Work | Phase::Rest => vec![],
+        }
+    }
+
+    pub fn pause(&mut self) {
+        self.running = false;
+    }
+
+    pub fn stop(&mut self) {
+        self.running = false;
+        self.phase = Phase::Idle;
+        self.round = 0;
+        self.remaining = self.round_length.into();
+    }
+
+    pub fn tick(&mut self, elapsed: Duration) -> Vec<TimerEvent> {
+        let mut events = vec![];
+        if !self.running {
+            return events;
+        }
+        let mut elapsed = elapsed;
+        while elapsed >= self.remaining {
+            elapsed -= self.remaining;
+            match self.phase {
+                Phase::Work => {
+                    events.push(TimerEvent::RoundEnded(self.round));
+                    if self.round >= self.rounds {
+                        self.phase = Phase::Finished;
+                        self.remaining = Duration::ZERO;
+                        self.running = false;
+                        events.push(TimerEvent::Finished);
+                        return events;
+                    }
+                    if self.rest_length.0 == 0 {
+                        self.start_round(self.round + 1);
+                        events.push(TimerEvent::RoundStarted(self.round));
+                    } else {
+                        self.phase = Phase::Rest;
+                        self.remaining = self.rest_length.into();
+                    }
+                }
+                Phase::Rest => {
+                    self.start_round(self.round + 1);
+                    events.push(TimerEvent::RoundStarted(self.round));
+                }
+                Phase::Idle | Phase::Finished => return events,
+            }
+        }
+        let before = self.remaining;
+        self.remaining -= elapsed;
+        if self.phase == Phase::Work && before > WARNING && self.remaining <= WARNING {
+            events.push(TimerEvent::Warning(self.round));
+        }
+        events
+    }
+
+    fn start_round(&mut self, round: usize) {
+        self.phase = Phase::Work;
+        self.round = round;
+        self.remaining = self.round_length.into();
+    }
+}
+
+impl Default for RoundTimer {
+    fn default() -> Self {
+        RoundTimer::new(Seconds(180), Seconds(60), 3)
+    }
+}
+
+impl From<Seconds> for Duration {
+    fn from(seconds: Seconds) -> Self {
+        Duration::from_secs(seconds.0)
+    }
+}
+
+impl fmt::Display for Seconds {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{:02}", self.0 / 60, self.0 % 60)
+    }
+}
+
+impl fmt::Display for Phase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Phase::Idle => write!(f, "Ready"),
+            Phase::Work => write!(f, "Work"),
+            Phase::Rest => write!(f, "Rest"),
+            Phase::Finished => write!(f, "Finished"),
+        }
+    }
+}
+
+impl fmt::Display for Pace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Pace::Off => write!(f, "Manual"),
+            Pace::Fixed(seconds) => write!(f, "Every {}s", seconds.0),
+            Pace::Random(min, max) => write!(f, "Random {}-{}s", min.0, max.0),
+            Pace::PerPunch { base, millis } => write!(
+                f,
+                "{}s + {}.{}s per punch",
+                base.0,
+                millis / 1000,
+                millis % 1000 / 100
+            ),
+        }
+    }
+}
+
+pub fn countdown(remaining: Duration) -> String {
+    let seconds = remaining.as_millis().div_ceil(1000) as u64;
+    Seconds(seconds).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rounds_and_rest() {
+        let mut timer = RoundTimer::new(Seconds(10), Seconds(5), 2);
+        assert_eq!(timer.start(), vec![TimerEvent::RoundStarted(1)]);
+        assert_eq!(timer.tick(Duration::from_secs(4)), vec![]);
+        assert_eq!(timer.remaining(), Duration::from_secs(6));
+        assert_eq!(
+            timer.tick(Duration::from_secs(7)),
+            vec![TimerEvent::RoundEnded(1)]
+        );
+        assert_eq!(timer.phase(), Phase::Rest);
+        assert_eq!(timer.remaining(), Duration::from_secs(4));
+        assert_eq!(
+            timer.tick(Duration::from_secs(4)),
+            vec![TimerEvent::RoundStarted(2)]
+        );
+        assert_eq!(timer.round(), 2);
+        assert_eq!(
+            timer.tick(Duration::from_secs(10)),
+            vec![TimerEvent::RoundEnded(2), TimerEvent::Finished]
+        );
+        assert_eq!(timer.phase(), Phase::Finished);
+        assert!(!timer.is_running());
+    }
+
+    #[test]
+    fn test_warning() {
+        let mut timer = RoundTimer::new(Seconds(30), Seconds(5), 2);
+        timer.start();
+        assert_eq!(timer.tick(Duration::from_secs(19)), vec![]);
+        assert_eq!(
+            timer.tick(Duration::from_secs(1)),
+            vec![TimerEvent::Warning(1)]
+        );
+        assert_eq!(timer.tick(Duration::from_secs(1)), vec![]);
+    }
+
+    #[test]
+    fn test_no_rest() {
+        let mut timer = RoundTimer::new(Seconds(10), Seconds(0), 3);
+        timer.start();
+        assert_eq!(
+            timer.tick(Duration::from_secs(10)),
+            vec![TimerEvent::RoundEnded(1), TimerEvent::RoundStarted(2)]
+        );
+    }
+
+    #[test]
+    fn test_pause_and_stop() {
+        let mut timer = RoundTimer::new(Seconds(10), Seconds(5), 2);
+        timer.start();
+        timer.pause();
+        assert_eq!(timer.tick(Duration::from_secs(20)), vec![]);
+        assert_eq!(timer.start(), vec![]);
+        assert_eq!(timer.remaining(), Duration::from_secs(10));
+        timer.stop();
+        assert_eq!(timer.phase(), Phase::Idle);
+        assert_eq!(timer.round(), 0);
+    }
+
+    #[test]
+    fn test_pace_interval() {
+        let mut rng = rand::thread_rng();
+        assert_eq!(Pace::Off.interval(3, &mut rng), None);
+        assert_eq!(
+            Pace::Fixed(Seconds(5)).interval(3, &mut rng),
+            Some(Duration::from_secs(5))
+        );
+        let random = Pace::Random(Seconds(3), Seconds(6))
+            .interval(3, &mut rng)
+            .unwrap();
+        assert!(random >= Duration::from_secs(3) && random <= Duration::from_secs(6));
+        assert_eq!(
+            PACES[7].interval(5, &mut rng),
+            Some(Duration::from_millis(5000))
+        );
+        assert_eq!(PACES[7].to_string(), "2s + 0.6s per punch");
+    }
+
+    #[test]
+    fn test_countdown() {
+        assert_eq!(countdown(Duration::from_millis(180_000)), "3:00");
+        assert_eq!(countdown(Duration::from_millis(59_100)), "1:00");
+        assert_eq!(countdown(Duration::from_millis(9_000)), "0:09");
+    }
+}
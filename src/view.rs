@@ -1,33 +1,147 @@
-use crate::model::{BodySelection, DefenceSelection, DistanceSelection, FaintSelection, Model};
+use crate::combination::{BODY_TAG, DEFENSE_TAG, Distance, FAINT_TAG, LONG};
+use crate::editor::{Editor, FLAGS, Flag};
+use crate::generator::{BODY_PERCENTS, COUNTS, Generator, LENGTHS, PUNCHES};
+use crate::journal::Journal;
+use crate::keys::{Action, Shortcut};
+use crate::model::Model;
+use crate::notation::Punch;
+use crate::practice::PRIORITIES;
+use crate::schedule::Rating;
+use crate::state::State;
+use crate::stats::{Count, Dashboard};
+use crate::tags::TagState;
+use crate::timer::{self, PACES, Pace, Phase, REST_LENGTHS, ROUND_COUNTS, ROUND_LENGTHS, Seconds};
+use std::path::PathBuf;
+
 use iced::{
-    Background, Color, Length, Task,
+    Background, Color, Element, Length, Size, Subscription, Task,
+    keyboard::{self, Key, Modifiers},
+    time::{self as iced_time, Duration, Instant},
+    window,
     widget::{
-        Button, Column, Scrollable, button, button::Style, column, radio, row,
-        scrollable::AbsoluteOffset, scrollable::scroll_to, text,
+        Button, Column, Row, Scrollable, button, button::Style, checkbox, column, pick_list, radio, row,
+        rich_text, scrollable::AbsoluteOffset, scrollable::scroll_to, span, text, text_input,
     },
 };
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub enum Message {
     Next,
     Previous,
     Reset,
     InOrder,
+    Weighted,
+    Due,
+    Rate(Rating),
     Reload,
-    DistanceSelected(DistanceSelection),
-    DefenceSelected(DefenceSelection),
-    FaintSelected(FaintSelection),
-    BodySelected(BodySelection),
+    TagSelected(String, TagState),
+    ClearTags,
+    QueryEdited(String),
+    SearchEdited(String),
+    PrioritySelected(u8),
+    SeedEdited(String),
+    SeedSubmitted,
+    PresetSelected(String),
+    PresetNameEdited(String),
+    SavePreset,
+    DeletePreset,
     ItemSelected(usize),
     Show,
+    Start,
+    Pause,
+    Stop,
+    Tick(Instant),
+    OpenJournal,
+    OpenDashboard,
+    OpenGenerator,
+    PunchToggled(u8),
+    MaxLengthSelected(usize),
+    BodyPercentSelected(u8),
+    CountSelected(usize),
+    DefenseRuleToggled(bool),
+    SameHandToggled(bool),
+    GeneratorLibrarySelected(String),
+    GenerateCombinations,
+    SaveGenerated,
+    DiscardGenerated,
+    CloseGenerator,
+    CloseDashboard,
+    SessionSelected(usize),
+    CloseJournal,
+    WindowResized(Size),
+    CloseRequested(window::Id),
+    RoundLengthSelected(Seconds),
+    RestLengthSelected(Seconds),
+    RoundsSelected(usize),
+    PaceSelected(Pace),
+    SpeechToggled(bool),
+    SoundToggled(bool),
+    KeyPressed(Shortcut),
+    DismissErrors,
+    NewCombination,
+    EditCombination,
+    DuplicateCombination,
+    LibraryEdited(String),
+    DescriptionEdited(String),
+    DistanceEdited(Distance),
+    DefenseEdited(Flag),
+    FaintEdited(Flag),
+    BodyEdited(Flag),
+    UrlEdited(String),
+    TagsEdited(String),
+    SaveCombination,
+    DeleteCombination,
+    CloseEditor,
 }
 
 const BUTTON_HIGHT: f32 = 30.0;
+const TICK_MILLIS: u64 = 100;
+const ERROR_COLOR: Color = Color::from_rgb(0.8, 0.0, 0.0);
+const INCLUDE_COLOR: Color = Color::from_rgb(0.0, 0.6, 0.0);
+const PRESET_NAME_WIDTH: f32 = 250.0;
+const SEED_WIDTH: f32 = 180.0;
+const TOP_COUNTS: usize = 10;
+const BAR: &str = "■";
+const BAR_WIDTH: usize = 50;
+const LENGTH_LABEL_WIDTH: f32 = 40.0;
+const STATS_COLOR: Color = Color::from_rgb(0.6, 0.6, 0.6);
+const HIGHLIGHT_COLOR: Color = Color::from_rgb(1.0, 0.9, 0.3);
 
-pub fn view(model: &Model) -> Column<Message> {
+pub fn view(model: &Model) -> Column<'_, Message> {
+    if let Some(editor) = model.editor() {
+        return editor_view(model, editor);
+    }
+    if let Some(journal) = model.journal() {
+        return journal_view(journal);
+    }
+    if let Some(dashboard) = model.dashboard() {
+        return dashboard_view(dashboard);
+    }
+    if let Some(generator) = model.generator() {
+        return generator_view(model, generator);
+    }
+    if model.is_empty() {
+        return empty_view(model);
+    }
     let mut column: Column<Message> = Column::new();
     for (index, item) in model.combinations().iter().enumerate() {
-        let mut button: Button<Message> = button(item.description.as_str())
+        let mut spans = vec![];
+        if model.has_libraries() {
+            spans.push(span(format!("[{}] ", item.library)));
+        }
+        let mut start = 0;
+        for range in model.highlights(item) {
+            spans.push(span(&item.description[start..range.start]));
+            spans.push(
+                span(&item.description[range.clone()])
+                    .color(Color::BLACK)
+                    .background(HIGHLIGHT_COLOR),
+            );
+            start = range.end;
+        }
+        spans.push(span(&item.description[start..]));
+        spans.push(span(format!("   {}", model.stats(item))).color(STATS_COLOR));
+        let mut button: Button<Message> = button(rich_text(spans))
             .on_press(Message::ItemSelected(index))
             .width(Length::Fill)
             .height(Length::Fixed(BUTTON_HIGHT));
@@ -44,11 +158,28 @@ pub fn view(model: &Model) -> Column<Message> {
     }
     let scrollable: Scrollable<Message> = Scrollable::new(column).id(model.scrollable_id().clone());
     let button_row_spacing = 5;
-    let row_spacing = 20;
-    let radio_label_length = 65;
-    let radio_length = 65;
+    let can_rate = model.current_combination().is_some();
+    let timer = model.timer();
+    let round_status = match timer.phase() {
+        Phase::Idle | Phase::Finished => timer.phase().to_string(),
+        phase => format!("Round {}/{} {}", timer.round(), timer.rounds(), phase),
+    };
     column![
-        text(model.number()).size(70).width(Length::Fill),
+        error_panel(model),
+        row![
+            text(model.number()).size(70),
+            text(if model.has_libraries() {
+                model.library()
+            } else {
+                "".to_owned()
+            })
+            .size(30)
+            .width(Length::Fill),
+            column![
+                text(round_status).size(30),
+                text(timer::countdown(timer.remaining())).size(100),
+            ],
+        ],
         text(model.combination()).size(100).width(Length::Fill),
         text("").size(10).width(Length::Fill),
         row![
@@ -56,129 +187,429 @@ pub fn view(model: &Model) -> Column<Message> {
             button("Previous")
                 .on_press(Message::Previous)
                 .width(Length::Fill),
+            button("Clean")
+                .on_press_maybe(can_rate.then_some(Message::Rate(Rating::Clean)))
+                .width(Length::Fill),
+            button("Sloppy")
+                .on_press_maybe(can_rate.then_some(Message::Rate(Rating::Sloppy)))
+                .width(Length::Fill),
+            button("Failed")
+                .on_press_maybe(can_rate.then_some(Message::Rate(Rating::Failed)))
+                .width(Length::Fill),
             button("Reset").on_press(Message::Reset).width(Length::Fill),
             button("In Order")
                 .on_press(Message::InOrder)
                 .width(Length::Fill),
+            button("Weighted")
+                .on_press(Message::Weighted)
+                .width(Length::Fill),
+            button("Due")
+                .on_press(Message::Due)
+                .width(Length::Fill),
             button("Reload")
                 .on_press(Message::Reload)
                 .width(Length::Fill),
             button("Show")
                 .on_press(Message::Show)
+                .width(Length::Fill),
+            button("Journal")
+                .on_press(Message::OpenJournal)
+                .width(Length::Fill),
+            button("Stats")
+                .on_press(Message::OpenDashboard)
+                .width(Length::Fill),
+            button("Generate")
+                .on_press(Message::OpenGenerator)
                 .width(Length::Fill),    
+            button("New")
+                .on_press(Message::NewCombination)
+                .width(Length::Fill),
+            button("Edit")
+                .on_press(Message::EditCombination)
+                .width(Length::Fill),
+            button("Duplicate")
+                .on_press(Message::DuplicateCombination)
+                .width(Length::Fill),
         ]
         .spacing(button_row_spacing),
         row![
-            text("Distance:").width(radio_label_length),
-            radio(
-                "All",
-                DistanceSelection::All,
-                model.distance_selection(),
-                Message::DistanceSelected
-            )
-            .width(radio_length),
-            radio(
-                "Long",
-                DistanceSelection::Long,
-                model.distance_selection(),
-                Message::DistanceSelected
-            )
-            .width(radio_length),
-            radio(
-                "Short",
-                DistanceSelection::Short,
-                model.distance_selection(),
-                Message::DistanceSelected
-            )
-            .width(radio_length),
+            button("Start").on_press(Message::Start).width(Length::Fill),
+            button("Pause").on_press(Message::Pause).width(Length::Fill),
+            button("Stop").on_press(Message::Stop).width(Length::Fill),
+            text("Round:"),
+            pick_list(
+                ROUND_LENGTHS,
+                Some(timer.round_length()),
+                Message::RoundLengthSelected
+            ),
+            text("Rest:"),
+            pick_list(
+                REST_LENGTHS,
+                Some(timer.rest_length()),
+                Message::RestLengthSelected
+            ),
+            text("Rounds:"),
+            pick_list(ROUND_COUNTS, Some(timer.rounds()), Message::RoundsSelected),
+            text("Callouts:"),
+            pick_list(PACES, Some(model.pace()), Message::PaceSelected),
+            checkbox("Speak", model.speech()).on_toggle(Message::SpeechToggled),
+            checkbox("Bells", model.sound()).on_toggle(Message::SoundToggled),
         ]
-        .spacing(row_spacing),
+        .spacing(button_row_spacing),
         row![
-            text("Defence:").width(radio_label_length),
-            radio(
-                "All",
-                DefenceSelection::All,
-                model.defence_selection(),
-                Message::DefenceSelected
-            )
-            .width(radio_length),
-            radio(
-                "Yes",
-                DefenceSelection::Yes,
-                model.defence_selection(),
-                Message::DefenceSelected
-            )
-            .width(radio_length),
-            radio(
-                "No",
-                DefenceSelection::No,
-                model.defence_selection(),
-                Message::DefenceSelected
-            )
-            .width(radio_length),
+            text("Priority:"),
+            pick_list(PRIORITIES, model.priority(), Message::PrioritySelected),
+            text("Seed:"),
+            text_input("Seed", model.seed_text())
+                .on_input(Message::SeedEdited)
+                .on_submit(Message::SeedSubmitted)
+                .width(Length::Fixed(SEED_WIDTH)),
+            button("Shuffle With Seed").on_press(Message::SeedSubmitted),
+            text("Preset:"),
+            pick_list(model.presets(), model.preset().cloned(), Message::PresetSelected),
+            text_input("Preset name", model.preset_name())
+                .on_input(Message::PresetNameEdited)
+                .width(Length::Fixed(PRESET_NAME_WIDTH)),
+            button("Save Preset").on_press(Message::SavePreset),
+            button("Delete Preset").on_press_maybe(model.preset().map(|_| Message::DeletePreset)),
+        ]
+        .spacing(button_row_spacing),
+        tag_row(model),
+        query_row(model),
+        text_input("Search descriptions and notes", model.search_text())
+            .on_input(Message::SearchEdited),
+        scrollable,
+    ]
+}
+
+// Each tag button cycles between any, include (+) and exclude (-).
+fn tag_row(model: &Model) -> Element<'_, Message> {
+    let mut tags: Row<Message> = row![text("Tags:")].spacing(5);
+    for tag in model.tags() {
+        let state = model.tag_state(&tag);
+        let (label, color) = match state {
+            TagState::Any => (tag.clone(), None),
+            TagState::Include => (format!("+{}", tag), Some(INCLUDE_COLOR)),
+            TagState::Exclude => (format!("-{}", tag), Some(ERROR_COLOR)),
+        };
+        let mut button = button(text(label)).on_press(Message::TagSelected(tag, state.cycle()));
+        if let Some(color) = color {
+            button = button.style(move |_, _| Style {
+                background: Some(Background::Color(color)),
+                text_color: Color::WHITE,
+                ..Style::default()
+            });
+        }
+        tags = tags.push(button);
+    }
+    tags.push(button("Clear").on_press_maybe(model.has_tag_filter().then_some(Message::ClearTags)))
+        .wrap()
+        .into()
+}
+
+fn query_row(model: &Model) -> Column<'_, Message> {
+    let input = text_input(
+        "Filter, e.g. distance:short and body and not defense or punches>=5",
+        model.query_text(),
+    )
+    .on_input(Message::QueryEdited);
+    let mut query = column![input];
+    if let Some(error) = model.query_error() {
+        query = query.push(text(error).color(ERROR_COLOR));
+    }
+    query
+}
+
+fn journal_view(journal: &Journal) -> Column<'_, Message> {
+    let mut sessions: Column<Message> = Column::new();
+    for (index, session) in journal.sessions.iter().enumerate() {
+        let mut button: Button<Message> = button(text(session.to_string()))
+            .on_press(Message::SessionSelected(index))
+            .width(Length::Fill);
+        if journal.selected == Some(index) {
+            button = button.style(button::primary);
+        } else {
+            button = button.style(button::secondary);
+        }
+        sessions = sessions.push(button);
+    }
+    let mut entries: Column<Message> = Column::new();
+    if let Some(session) = journal.session() {
+        for entry in &session.entries {
+            entries = entries.push(text(entry.to_string()));
+        }
+        if let Some(end) = session.end() {
+            entries = entries.push(text(end));
+        }
+    }
+    column![
+        row![
+            text("Journal").size(50).width(Length::Fill),
+            button("Close").on_press(Message::CloseJournal),
+        ],
+        if journal.sessions.is_empty() {
+            text("No sessions yet")
+        } else {
+            text("")
+        },
+        Scrollable::new(sessions).height(Length::FillPortion(1)),
+        Scrollable::new(entries).height(Length::FillPortion(2)),
+    ]
+    .spacing(10)
+}
+
+fn dashboard_view(dashboard: &Dashboard) -> Column<'_, Message> {
+    let mut fields: Row<Message> = Row::new().spacing(40);
+    for (name, counts) in &dashboard.fields {
+        fields = fields.push(count_column(name, counts));
+    }
+    let longest = dashboard.lengths.values().copied().max().unwrap_or_default();
+    let mut lengths: Column<Message> = column![text("Punches per combination").size(30)];
+    for (punches, count) in &dashboard.lengths {
+        lengths = lengths.push(row![
+            text(punches.to_string()).width(Length::Fixed(LENGTH_LABEL_WIDTH)),
+            text(bar(*count, longest)),
+            text(count.to_string()),
         ]
-        .spacing(row_spacing),
+        .spacing(10));
+    }
+    if dashboard.unparsed > 0 {
+        lengths = lengths.push(text(format!("{} not in punch notation", dashboard.unparsed)));
+    }
+    column![
         row![
-            text("Faint:").width(radio_label_length),
-            radio(
-                "All",
-                FaintSelection::All,
-                model.faint_selection(),
-                Message::FaintSelected
-            )
-            .width(radio_length),
-            radio(
-                "Yes",
-                FaintSelection::Yes,
-                model.faint_selection(),
-                Message::FaintSelected
-            )
-            .width(radio_length),
-            radio(
-                "No",
-                FaintSelection::No,
-                model.faint_selection(),
-                Message::FaintSelected
-            )
-            .width(radio_length),
+            text(format!("Statistics of {} combinations", dashboard.total))
+                .size(50)
+                .width(Length::Fill),
+            button("Close").on_press(Message::CloseDashboard),
+        ],
+        Scrollable::new(
+            column![
+                fields,
+                lengths,
+                row![
+                    count_column("Punches", &dashboard.punches),
+                    count_column("Leads", &dashboard.leads),
+                    count_column("Transitions", &dashboard.transitions),
+                ]
+                .spacing(40),
+            ]
+            .spacing(20)
+        ),
+    ]
+    .spacing(10)
+}
+
+fn count_column<'a>(title: &'a str, counts: &'a [Count]) -> Column<'a, Message> {
+    let mut column: Column<Message> = column![text(title).size(30)];
+    for (name, count) in counts.iter().take(TOP_COUNTS) {
+        column = column.push(text(format!("{}: {}", name, count)));
+    }
+    column
+}
+
+fn bar(count: usize, longest: usize) -> String {
+    BAR.repeat((count * BAR_WIDTH).div_ceil(longest.max(1)))
+}
+
+fn generator_view<'a>(model: &'a Model, generator: &'a Generator) -> Column<'a, Message> {
+    let rules = &generator.rules;
+    let mut punches: Row<Message> = row![text("Punches:")].spacing(20);
+    for number in PUNCHES {
+        let punch = Punch {
+            number,
+            body: false,
+            feint: false,
+        };
+        punches = punches.push(
+            checkbox(format!("{} {}", number, punch.name()), rules.punches.contains(&number))
+                .on_toggle(move |_| Message::PunchToggled(number)),
+        );
+    }
+    let mut generated: Column<Message> = Column::new();
+    for combination in model.generated() {
+        generated = generated.push(text(combination.description.clone()).size(20));
+    }
+    let libraries: Vec<String> = model
+        .libraries()
+        .iter()
+        .map(|path| path.display().to_string())
+        .collect();
+    let has_generated = !model.generated().is_empty();
+    column![
+        error_panel(model),
+        text("Generate combinations").size(70),
+        punches,
+        row![
+            text("Max punches:"),
+            pick_list(LENGTHS, Some(rules.max_length), Message::MaxLengthSelected),
+            text("Body shots (%):"),
+            pick_list(BODY_PERCENTS, Some(rules.body_percent), Message::BodyPercentSelected),
+            text("Count:"),
+            pick_list(COUNTS, Some(rules.count), Message::CountSelected),
         ]
-        .spacing(row_spacing),
+        .spacing(5),
+        checkbox("Include a defensive move", rules.defense).on_toggle(Message::DefenseRuleToggled),
+        checkbox(
+            "Avoid the same hand twice in a row (double jabs are fine)",
+            rules.avoid_same_hand
+        )
+        .on_toggle(Message::SameHandToggled),
         row![
-            text("Body:").width(radio_label_length),
-            radio(
-                "All",
-                BodySelection::All,
-                model.body_selection(),
-                Message::BodySelected
-            )
-            .width(radio_length),
+            button("Generate").on_press(Message::GenerateCombinations),
+            text(format!("{} generated, shown in the list until saved", model.generated().len())),
+        ]
+        .spacing(10),
+        Scrollable::new(generated).height(Length::Fill),
+        row![
+            text("Save to:"),
+            pick_list(
+                libraries,
+                Some(generator.library.display().to_string()),
+                Message::GeneratorLibrarySelected
+            ),
+            button("Save All").on_press_maybe(has_generated.then_some(Message::SaveGenerated)),
+            button("Discard").on_press_maybe(has_generated.then_some(Message::DiscardGenerated)),
+            button("Close").on_press(Message::CloseGenerator),
+        ]
+        .spacing(5),
+    ]
+    .spacing(10)
+}
+
+fn empty_view(model: &Model) -> Column<'_, Message> {
+    let mut libraries: Column<Message> = Column::new();
+    for path in model.libraries() {
+        libraries = libraries.push(text(path.display().to_string()));
+    }
+    column![
+        text("No combinations loaded").size(70),
+        libraries,
+        error_panel(model),
+        row![
+            button("Reload").on_press(Message::Reload),
+            button("New").on_press(Message::NewCombination),
+            button("Generate").on_press(Message::OpenGenerator),
+        ]
+        .spacing(5),
+    ]
+    .spacing(10)
+}
+
+fn editor_view<'a>(model: &'a Model, editor: &'a Editor) -> Column<'a, Message> {
+    let validated = editor.validate();
+    let mut validation: Column<Message> = Column::new();
+    match &validated {
+        Ok(combination) => {
+            validation = validation.push(match combination.notation() {
+                Ok(notation) => text(format!("Callout: {}", notation.callout())),
+                Err(e) => text(format!("Notation: {}", e)).color(ERROR_COLOR),
+            });
+            for warning in combination.inconsistencies() {
+                validation = validation.push(text(format!("Warning: {}", warning)).color(ERROR_COLOR));
+            }
+        }
+        Err(e) => validation = validation.push(text(e.to_string()).color(ERROR_COLOR)),
+    }
+    let library = editor.library().display().to_string();
+    let (title, library_row) = if editor.is_new() {
+        let libraries: Vec<String> = model
+            .libraries()
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect();
+        (
+            "New combination",
+            row![
+                text("Library:"),
+                pick_list(libraries, Some(library), Message::LibraryEdited),
+            ],
+        )
+    } else {
+        ("Edit combination", row![text(format!("Library: {}", library))])
+    };
+    let mut buttons = row![
+        button("Save").on_press_maybe(validated.ok().map(|_| Message::SaveCombination))
+    ];
+    if !editor.is_new() {
+        buttons = buttons.push(button("Delete").on_press(Message::DeleteCombination));
+    }
+    buttons = buttons.push(button("Cancel").on_press(Message::CloseEditor));
+    column![
+        error_panel(model),
+        text(title).size(70),
+        library_row.spacing(5),
+        text("Description:"),
+        text_input("1-2-slip_left-2", editor.description())
+            .on_input(Message::DescriptionEdited)
+            .size(30),
+        validation,
+        row![
+            text("Distance:"),
             radio(
-                "Yes",
-                BodySelection::Yes,
-                model.body_selection(),
-                Message::BodySelected
-            )
-            .width(radio_length),
+                "Long",
+                Distance::Long,
+                Some(editor.distance()),
+                Message::DistanceEdited
+            ),
             radio(
-                "No",
-                BodySelection::No,
-                model.body_selection(),
-                Message::BodySelected
-            )
-            .width(radio_length),
+                "Short",
+                Distance::Short,
+                Some(editor.distance()),
+                Message::DistanceEdited
+            ),
         ]
-        .spacing(row_spacing),
-        scrollable,
+        .spacing(20),
+        row![
+            text("Defence:"),
+            pick_list(FLAGS, Some(editor.defense()), Message::DefenseEdited),
+            text("Faint:"),
+            pick_list(FLAGS, Some(editor.faint()), Message::FaintEdited),
+            text("Body:"),
+            pick_list(FLAGS, Some(editor.body()), Message::BodyEdited),
+        ]
+        .spacing(5),
+        text("Tags:"),
+        text_input("counter, southpaw", editor.tags()).on_input(Message::TagsEdited),
+        text("URL:"),
+        text_input("https://", editor.url()).on_input(Message::UrlEdited),
+        buttons.spacing(5),
     ]
+    .spacing(10)
+}
+
+fn error_panel(model: &Model) -> Column<'_, Message> {
+    let mut panel: Column<Message> = Column::new();
+    if model.errors().is_empty() {
+        return panel;
+    }
+    for error in model.errors() {
+        panel = panel.push(text(error).color(ERROR_COLOR));
+    }
+    panel.push(button("Dismiss").on_press(Message::DismissErrors))
+}
+
+// Starts where the last session stopped.
+pub fn boot(state: State) -> (Model, Task<Message>) {
+    let model = Model::new(state);
+    let task = scroll_task(&model);
+    (model, task)
 }
 
 pub fn update(model: &mut Model, message: Message) -> Task<Message> {
+    let before = (model.number(), model.combination());
+    let mut scroll = true;
+    // Filter changes also put another combination on screen, but only moving
+    // on from one counts as practice.
+    let mut navigated = false;
     match message {
         Message::Next => {
             model.next();
+            navigated = true;
         }
         Message::Previous => {
             model.previous();
+            navigated = true;
         }
         Message::Reset => {
             model.reset_in_random_order();
@@ -186,29 +617,302 @@ pub fn update(model: &mut Model, message: Message) -> Task<Message> {
         Message::InOrder => {
             model.reset_in_order();
         }
+        Message::Weighted => {
+            model.reset_weighted();
+        }
+        Message::Due => {
+            model.reset_due();
+        }
+        Message::Rate(rating) => {
+            model.rate(rating);
+            navigated = true;
+        }
         Message::Reload => {
             model.reload();
         }
-        Message::DistanceSelected(option) => {
-            model.set_distance_selection(option);
+        Message::TagSelected(tag, state) => {
+            model.set_tag_state(&tag, state);
         }
-        Message::DefenceSelected(option) => {
-            model.set_defence_selection(option);
+        Message::ClearTags => {
+            model.clear_tag_filter();
         }
-        Message::FaintSelected(option) => {
-            model.set_faint_selection(option);
+        Message::QueryEdited(text) => {
+            model.set_query(text);
         }
-        Message::BodySelected(option) => {
-            model.set_body_selection(option);
+        Message::SearchEdited(text) => {
+            model.set_search(text);
+        }
+        Message::PrioritySelected(priority) => {
+            model.set_priority(priority);
+        }
+        Message::SeedEdited(text) => {
+            model.set_seed_text(text);
+        }
+        Message::SeedSubmitted => {
+            model.apply_seed_text();
+        }
+        Message::PresetSelected(name) => {
+            model.apply_preset(name);
+        }
+        Message::PresetNameEdited(name) => {
+            model.set_preset_name(name);
+        }
+        Message::SavePreset => {
+            model.save_preset();
+        }
+        Message::DeletePreset => {
+            model.delete_preset();
         }
         Message::ItemSelected(index) => {
             model.set(index);
+            navigated = true;
+        }
+        Message::OpenJournal => {
+            model.open_journal();
+        }
+        Message::SessionSelected(index) => {
+            model.select_session(index);
+        }
+        Message::CloseJournal => {
+            model.close_journal();
+        }
+        Message::OpenDashboard => {
+            model.open_dashboard();
+        }
+        Message::CloseDashboard => {
+            model.close_dashboard();
+        }
+        Message::OpenGenerator => {
+            model.open_generator();
+        }
+        Message::PunchToggled(punch) => {
+            if let Some(generator) = model.generator_mut() {
+                generator.rules.toggle_punch(punch);
+            }
+        }
+        Message::MaxLengthSelected(length) => {
+            if let Some(generator) = model.generator_mut() {
+                generator.rules.max_length = length;
+            }
+        }
+        Message::BodyPercentSelected(percent) => {
+            if let Some(generator) = model.generator_mut() {
+                generator.rules.body_percent = percent;
+            }
+        }
+        Message::CountSelected(count) => {
+            if let Some(generator) = model.generator_mut() {
+                generator.rules.count = count;
+            }
+        }
+        Message::DefenseRuleToggled(defense) => {
+            if let Some(generator) = model.generator_mut() {
+                generator.rules.defense = defense;
+            }
+        }
+        Message::SameHandToggled(avoid) => {
+            if let Some(generator) = model.generator_mut() {
+                generator.rules.avoid_same_hand = avoid;
+            }
+        }
+        Message::GeneratorLibrarySelected(library) => {
+            if let Some(generator) = model.generator_mut() {
+                generator.library = PathBuf::from(library);
+            }
+        }
+        Message::GenerateCombinations => {
+            model.generate();
+        }
+        Message::SaveGenerated => {
+            model.save_generated();
+        }
+        Message::DiscardGenerated => {
+            model.discard_generated();
+        }
+        Message::CloseGenerator => {
+            model.close_generator();
         }
         Message::Show => {
             model.show();
         }
+        Message::Start => {
+            let events = model.start_timer();
+            model.play_cues(&events);
+        }
+        Message::Pause => {
+            model.pause_timer();
+        }
+        Message::Stop => {
+            model.stop_timer();
+        }
+        Message::Tick(now) => {
+            let events = model.tick(now);
+            model.play_cues(&events);
+            scroll = false;
+        }
+        Message::WindowResized(size) => {
+            model.set_window_size(size);
+            scroll = false;
+        }
+        Message::CloseRequested(id) => {
+            model.save_state();
+            model.end_session();
+            return window::close(id);
+        }
+        Message::RoundLengthSelected(option) => {
+            model.set_round_length(option);
+        }
+        Message::RestLengthSelected(option) => {
+            model.set_rest_length(option);
+        }
+        Message::RoundsSelected(option) => {
+            model.set_rounds(option);
+        }
+        Message::PaceSelected(option) => {
+            model.set_pace(option);
+        }
+        Message::SpeechToggled(option) => {
+            model.set_speech(option);
+        }
+        Message::SoundToggled(option) => {
+            model.set_sound(option);
+        }
+        Message::DismissErrors => {
+            model.dismiss_errors();
+        }
+        Message::NewCombination => {
+            model.new_combination();
+        }
+        Message::EditCombination => {
+            model.edit_combination();
+        }
+        Message::DuplicateCombination => {
+            model.duplicate_combination();
+        }
+        Message::LibraryEdited(library) => {
+            if let Some(editor) = model.editor_mut() {
+                editor.set_library(PathBuf::from(library));
+            }
+        }
+        Message::DescriptionEdited(description) => {
+            if let Some(editor) = model.editor_mut() {
+                editor.set_description(description);
+            }
+        }
+        Message::DistanceEdited(distance) => {
+            if let Some(editor) = model.editor_mut() {
+                editor.set_distance(distance);
+            }
+        }
+        Message::DefenseEdited(flag) => {
+            if let Some(editor) = model.editor_mut() {
+                editor.set_defense(flag);
+            }
+        }
+        Message::FaintEdited(flag) => {
+            if let Some(editor) = model.editor_mut() {
+                editor.set_faint(flag);
+            }
+        }
+        Message::BodyEdited(flag) => {
+            if let Some(editor) = model.editor_mut() {
+                editor.set_body(flag);
+            }
+        }
+        Message::UrlEdited(url) => {
+            if let Some(editor) = model.editor_mut() {
+                editor.set_url(url);
+            }
+        }
+        Message::TagsEdited(tags) => {
+            if let Some(editor) = model.editor_mut() {
+                editor.set_tags(tags);
+            }
+        }
+        Message::SaveCombination => {
+            model.save_combination();
+        }
+        Message::DeleteCombination => {
+            model.delete_combination();
+        }
+        Message::CloseEditor => {
+            model.close_editor();
+        }
+        // Shortcuts only work on the main screen.
+        Message::KeyPressed(_)
+            if model.editor().is_some()
+                || model.journal().is_some()
+                || model.dashboard().is_some()
+                || model.generator().is_some() =>
+        {
+            return Task::none();
+        }
+        Message::KeyPressed(shortcut) => {
+            return match model.action(shortcut) {
+                Some(action) => {
+                    let message = action_message(model, action);
+                    update(model, message)
+                }
+                None => Task::none(),
+            };
+        }
+    }
+    if before != (model.number(), model.combination()) {
+        if navigated {
+            model.moved();
+        }
+        scroll = true;
     }
-    scroll_task(model)
+    if scroll {
+        scroll_task(model)
+    } else {
+        Task::none()
+    }
+}
+
+pub fn subscription(model: &Model) -> Subscription<Message> {
+    let timer = if model.timer().is_running() {
+        iced_time::every(Duration::from_millis(TICK_MILLIS)).map(Message::Tick)
+    } else {
+        Subscription::none()
+    };
+    Subscription::batch([
+        keyboard::on_key_press(key_pressed),
+        window::resize_events().map(|(_, size)| Message::WindowResized(size)),
+        window::close_requests().map(Message::CloseRequested),
+        timer,
+    ])
+}
+
+fn key_pressed(key: Key, _modifiers: Modifiers) -> Option<Message> {
+    Shortcut::from_key(&key).map(Message::KeyPressed)
+}
+
+fn action_message(model: &Model, action: Action) -> Message {
+    match action {
+        Action::Next => Message::Next,
+        Action::Previous => Message::Previous,
+        Action::Clean => Message::Rate(Rating::Clean),
+        Action::Sloppy => Message::Rate(Rating::Sloppy),
+        Action::Failed => Message::Rate(Rating::Failed),
+        Action::Reset => Message::Reset,
+        Action::InOrder => Message::InOrder,
+        Action::Weighted => Message::Weighted,
+        Action::Due => Message::Due,
+        Action::Reload => Message::Reload,
+        Action::Show => Message::Show,
+        Action::Start => Message::Start,
+        Action::Pause => Message::Pause,
+        Action::Stop => Message::Stop,
+        Action::Distance => tag_message(model, LONG),
+        Action::Defence => tag_message(model, DEFENSE_TAG),
+        Action::Faint => tag_message(model, FAINT_TAG),
+        Action::Body => tag_message(model, BODY_TAG),
+    }
+}
+
+fn tag_message(model: &Model, tag: &str) -> Message {
+    Message::TagSelected(tag.to_owned(), model.tag_state(tag).cycle())
 }
 
 fn scroll_task(model: &Model) -> Task<Message> {